@@ -1,7 +1,7 @@
-use bevy::{math::{uvec2, vec3}, prelude::*, render::view::visibility};
+use bevy::{math::{uvec2, vec3}, prelude::*};
 use rand::Rng;
 
-use crate::core::functions::TextureAtlasLayoutHandles;
+use crate::core::{functions::TextureAtlasLayoutHandles, rng::GameRng};
 
 
 
@@ -19,12 +19,9 @@ pub struct AnimationController{
     */
     pub priority: usize,
     direction: usize,
-    armed: bool
-}
-
-enum EyeStateOverride{
-    Scared,
-    Angry
+    armed: bool,
+    /// Seconds spent as a bat, `None` in the usual form.
+    bat: Option<f32>,
 }
 
 impl Default for AnimationController{
@@ -36,7 +33,8 @@ impl Default for AnimationController{
             dir_offset: 7,
             priority: 0,
             direction: 0,
-            armed: false
+            armed: false,
+            bat: None,
         }
     }
 }
@@ -59,11 +57,24 @@ pub fn spawn_player_animation_bundle(commands: &mut Commands, asset_server: &Res
                     ..default()
                 },
                 TextureAtlas{
-                    layout: layout_handles.add_or_load(&asset_server, "Vampire", TextureAtlasLayout::from_grid(uvec2(14, 20), 7, 3, Some(uvec2(1, 1)), None)),
+                    layout: layout_handles.add_or_load(asset_server, "Vampire", TextureAtlasLayout::from_grid(uvec2(14, 20), 7, 3, Some(uvec2(1, 1)), None)),
                     index: 2
                 },
                 PartType::Body{variant: 0, variants: 1},
             ));
+            commands.spawn((
+                Name::new("Bat"),
+                PartType::Bat,
+                SpriteBundle{
+                    texture: asset_server.load("player/bat.png"),
+                    visibility: Visibility::Hidden,
+                    ..default()
+                },
+                TextureAtlas{
+                    layout: layout_handles.add_or_load(asset_server, "Bat", TextureAtlasLayout::from_grid(uvec2(16, 16), BAT_FRAMES as u32, 1, None, None)),
+                    index: 0
+                },
+            ));
             commands.spawn((
                 Name::new("Items"),
             ));
@@ -82,14 +93,14 @@ pub fn spawn_player_animation_bundle(commands: &mut Commands, asset_server: &Res
                     ..default()
                 },
                 TextureAtlas{
-                    layout: layout_handles.add_or_load(&asset_server, "Umbrella", TextureAtlasLayout::from_grid(uvec2(19, 13), 2, 3, Some(uvec2(1, 1)), None)),
+                    layout: layout_handles.add_or_load(asset_server, "Umbrella", TextureAtlasLayout::from_grid(uvec2(19, 13), 2, 3, Some(uvec2(1, 1)), None)),
                     index: 0
                 },
             )).insert(Transform::from_translation(vec3(0., 3., ITEM_Z)));
     }).id()
 }
 
-pub fn spawn_hunter_animation_bundle(mut commands: &mut Commands, asset_server: &Res<AssetServer>, layout_handles: &mut ResMut<TextureAtlasLayoutHandles>) -> Entity{
+pub fn spawn_hunter_animation_bundle(commands: &mut Commands, asset_server: &Res<AssetServer>, layout_handles: &mut ResMut<TextureAtlasLayoutHandles>) -> Entity{
     commands.spawn((
         AnimationController{
             ..default()
@@ -105,7 +116,7 @@ pub fn spawn_hunter_animation_bundle(mut commands: &mut Commands, asset_server:
                 ..default()
             },
             TextureAtlas{
-                layout: layout_handles.add_or_load(&asset_server, "Hunter", TextureAtlasLayout::from_grid(uvec2(16, 20), 7, 3, Some(uvec2(1, 1)), None)),
+                layout: layout_handles.add_or_load(asset_server, "Hunter", TextureAtlasLayout::from_grid(uvec2(16, 20), 7, 3, Some(uvec2(1, 1)), None)),
                 index: 2
             },
         ));
@@ -134,8 +145,9 @@ const BODY_COUNT : usize = 8;
 const OUTFIT_COUNT : usize = 6;
 const HAIR_COUNT : usize = 36;
 const EYE_COUNT : usize = 6;
-const EYE_PUB_COUNT : usize = 3;
 const WEAPON_COUNT : usize = 2;
+pub const BAT_FRAMES : usize = 3;
+const BAT_FRAME_TIME : f32 = 0.08;
 
 
 
@@ -148,7 +160,8 @@ pub enum PartType{
     Outfit{variant: usize, variants: usize},
     Arms,
     Umbrella,
-    Hair{variant: usize, variants: usize}
+    Hair{variant: usize, variants: usize},
+    Bat,
 }
 
 impl PartType {
@@ -164,6 +177,7 @@ impl PartType {
                 PartType::Outfit { variant: _, variants: _ } => OUTFIT_Z,
                 PartType::Arms => ARMS_Z,
                 PartType::Hair { variant: _, variants: _ } => HAIR_Z,
+                PartType::Bat => BODY_Z,
             }
         )
     }
@@ -173,19 +187,20 @@ impl PartType {
 
 
 
-pub fn spawn_civilian_animation_bundle(commands: &mut Commands, asset_server: &Res<AssetServer>, layout_handles: &mut ResMut<TextureAtlasLayoutHandles>) -> Entity {
-    let body_variant = rand::thread_rng().gen_range(0..BODY_COUNT);
-    let outfit_variant = rand::thread_rng().gen_range(0..OUTFIT_COUNT);
-    let hair_variant = rand::thread_rng().gen_range(0..HAIR_COUNT);
-    let weapon_variant = rand::thread_rng().gen_range(0..WEAPON_COUNT);
+pub fn spawn_civilian_animation_bundle(commands: &mut Commands, asset_server: &Res<AssetServer>, layout_handles: &mut ResMut<TextureAtlasLayoutHandles>, rng: &mut GameRng) -> Entity {
+    let rng = rng.cosmetic();
+    let body_variant = rng.gen_range(0..BODY_COUNT);
+    let outfit_variant = rng.gen_range(0..OUTFIT_COUNT);
+    let hair_variant = rng.gen_range(0..HAIR_COUNT);
+    let weapon_variant = rng.gen_range(0..WEAPON_COUNT);
 
-    let main_color = rand::thread_rng().gen::<f32>() * 0.25 + 0.5;
-    let second_color = rand::thread_rng().gen::<f32>() * 0.5;
-    let third_color = rand::thread_rng().gen::<f32>() * 0.2;
+    let main_color = rng.gen::<f32>() * 0.25 + 0.5;
+    let second_color = rng.gen::<f32>() * 0.5;
+    let third_color = rng.gen::<f32>() * 0.2;
 
-    let main_idx = rand::thread_rng().gen_range(0..=2);
-    let second_idx = rand::thread_rng().gen_range(0..=2);
-    let third_idx = rand::thread_rng().gen_range(0..=2);
+    let main_idx = rng.gen_range(0..=2);
+    let second_idx = rng.gen_range(0..=2);
+    let third_idx = rng.gen_range(0..=2);
     let mut eye_color = [0.; 3];
     eye_color[main_idx] = main_color;
     eye_color[second_idx] = (eye_color[second_idx] + second_color).clamp(0., 1.); 
@@ -207,7 +222,7 @@ pub fn spawn_civilian_animation_bundle(commands: &mut Commands, asset_server: &R
                     ..default()
             },
             TextureAtlas{
-                layout: layout_handles.add_or_load(&asset_server, "Body", TextureAtlasLayout::from_grid(uvec2(14, 18), BODY_COUNT as u32 * 4, 3, Some(uvec2(1, 1)), None)),
+                layout: layout_handles.add_or_load(asset_server, "Body", TextureAtlasLayout::from_grid(uvec2(14, 18), BODY_COUNT as u32 * 4, 3, Some(uvec2(1, 1)), None)),
                 index: body_variant * 3 + 1
             },
         )).insert(Transform::from_translation(vec3(0., 0., BODY_Z)));
@@ -219,7 +234,7 @@ pub fn spawn_civilian_animation_bundle(commands: &mut Commands, asset_server: &R
                 ..default()
             },
             TextureAtlas{
-                layout: layout_handles.add_or_load(&asset_server, "Weapon", TextureAtlasLayout::from_grid(uvec2(26, 22), WEAPON_COUNT as u32 * 5, 3, Some(uvec2(1, 1)), None)),
+                layout: layout_handles.add_or_load(asset_server, "Weapon", TextureAtlasLayout::from_grid(uvec2(26, 22), WEAPON_COUNT as u32 * 5, 3, Some(uvec2(1, 1)), None)),
                 index: 2 * weapon_variant
             },
         )).insert(Transform::from_translation(vec3(-1.5, 1., ITEM_Z)));
@@ -231,7 +246,7 @@ pub fn spawn_civilian_animation_bundle(commands: &mut Commands, asset_server: &R
                 ..default()
             },
             TextureAtlas{
-                layout: layout_handles.add_or_load(&asset_server, "Outfit", TextureAtlasLayout::from_grid(uvec2(14, 18), OUTFIT_COUNT as u32 * 4, 3, Some(uvec2(1, 1)), None)),
+                layout: layout_handles.add_or_load(asset_server, "Outfit", TextureAtlasLayout::from_grid(uvec2(14, 18), OUTFIT_COUNT as u32 * 4, 3, Some(uvec2(1, 1)), None)),
                 index: outfit_variant * 4 + 1
             },
         )).insert(Transform::from_translation(vec3(0., 0., OUTFIT_Z)));
@@ -243,7 +258,7 @@ pub fn spawn_civilian_animation_bundle(commands: &mut Commands, asset_server: &R
                     ..default()
             },
             TextureAtlas{
-                layout: layout_handles.add_or_load(&asset_server, "Arms", TextureAtlasLayout::from_grid(uvec2(14, 18), BODY_COUNT as u32 * 4, 3, Some(uvec2(1, 1)), Some(uvec2(0, 57)))),
+                layout: layout_handles.add_or_load(asset_server, "Arms", TextureAtlasLayout::from_grid(uvec2(14, 18), BODY_COUNT as u32 * 4, 3, Some(uvec2(1, 1)), Some(uvec2(0, 57)))),
                 index: outfit_variant * 4 + 1
             },
         )).insert(Transform::from_translation(vec3(0., 0., ARMS_Z)));
@@ -255,7 +270,7 @@ pub fn spawn_civilian_animation_bundle(commands: &mut Commands, asset_server: &R
                     ..default()
                 },
                 TextureAtlas{
-                    layout: layout_handles.add_or_load(&asset_server, "Eyes", TextureAtlasLayout::from_grid(uvec2(8, 4), EYE_COUNT as u32, 3, Some(uvec2(1, 1)), None)),
+                    layout: layout_handles.add_or_load(asset_server, "Eyes", TextureAtlasLayout::from_grid(uvec2(8, 4), EYE_COUNT as u32, 3, Some(uvec2(1, 1)), None)),
                     index: 0
                 },
         )).insert(Transform::from_translation(vec3(0., 0., EYES_Z)));
@@ -272,7 +287,7 @@ pub fn spawn_civilian_animation_bundle(commands: &mut Commands, asset_server: &R
                     ..default()
                 },
                 TextureAtlas{
-                    layout: layout_handles.add_or_load(&asset_server, "Eyes", TextureAtlasLayout::from_grid(uvec2(8, 4), EYE_COUNT as u32, 3, Some(uvec2(1, 1)), None)),
+                    layout: layout_handles.add_or_load(asset_server, "Eyes", TextureAtlasLayout::from_grid(uvec2(8, 4), EYE_COUNT as u32, 3, Some(uvec2(1, 1)), None)),
                     index: 1
                 },
         )).insert(Transform::from_translation(vec3(0., 0., EYES_Z)));
@@ -285,7 +300,7 @@ pub fn spawn_civilian_animation_bundle(commands: &mut Commands, asset_server: &R
                 ..default()
             },
             TextureAtlas{
-                layout: layout_handles.add_or_load(&asset_server, "Hair", TextureAtlasLayout::from_grid(uvec2(14, 18), HAIR_COUNT as u32, 4, Some(uvec2(1, 1)), None)),
+                layout: layout_handles.add_or_load(asset_server, "Hair", TextureAtlasLayout::from_grid(uvec2(14, 18), HAIR_COUNT as u32, 4, Some(uvec2(1, 1)), None)),
                 index: hair_variant
             },
         )).insert(Transform::from_translation(vec3(0., 0., HAIR_Z)));
@@ -320,13 +335,13 @@ impl AnimTicker{
         self.frame += dt;
     }
 
-    fn get<T: Clone>(&self, frametime: &FrameTime, vec: &Vec<T>) -> Option<T> {
-        if vec.len() == 0 {return None}
+    fn get<T: Clone>(&self, frametime: &FrameTime, vec: &[T]) -> Option<T> {
+        if vec.is_empty() {return None}
         match frametime {
             FrameTime::Constant(t) => {
                 let current_anim_frame = (self.frame / t).floor() as usize;
                 if current_anim_frame >= vec.len() {return vec.last().cloned()}
-                return vec.get(current_anim_frame).cloned()
+                vec.get(current_anim_frame).cloned()
             },
             FrameTime::Sequence(v) => {
                 let mut ac = 0.;
@@ -334,12 +349,12 @@ impl AnimTicker{
                 for (i, t) in v.iter().enumerate() {
                     if ac >= self.frame {
                         if i >= vec.len() {return vec.last().cloned()}
-                        return vec.get(i.checked_sub(1).unwrap_or(0)).cloned()
+                        return vec.get(i.saturating_sub(1)).cloned()
                     }
                     ac += t;
                     lasti = i;
                 }
-                return vec.get(lasti).cloned()
+                vec.get(lasti).cloned()
             },
         }
     }
@@ -402,6 +417,14 @@ impl AnimationController{
         self.ticker.to_start();
     }
 
+    /// Leaning into the victim until the feed ends, nothing short of a hurt interrupts it.
+    pub fn play_feed(&mut self){
+        self.current_animation = CharacterAnimation::simple(FrameTime::Constant(0.4), vec![1, 2]).looped()
+            .with_offsets(vec![vec3(0., 0., 0.), vec3(0., -1., 0.)]);
+        self.priority = 2;
+        self.ticker.to_start();
+    }
+
     pub fn play_hurt(&mut self){
         self.current_animation = CharacterAnimation::simple(FrameTime::Constant(0.2), vec![3]);
         self.priority = 3;
@@ -439,6 +462,11 @@ impl AnimationController{
         self.direction = 0;
     }
 
+    /// The item is out and in hand, the umbrella for the player.
+    pub fn is_armed(&self) -> bool {
+        self.armed && !self.is_bat()
+    }
+
     pub fn arm(&mut self){
         self.armed = true
     }
@@ -508,7 +536,33 @@ impl AnimationController{
         idx + d * offset
     }
 
+    pub fn become_bat(&mut self){
+        self.bat = Some(0.);
+    }
+
+    pub fn become_vampire(&mut self){
+        self.bat = None;
+    }
+
+    pub fn is_bat(&self) -> bool {
+        self.bat.is_some()
+    }
+
+    pub fn get_bat_idx(&self) -> usize {
+        let t = self.bat.unwrap_or(0.);
+        (t / BAT_FRAME_TIME) as usize % BAT_FRAMES
+    }
+
+    pub fn get_bat_visibility(&self) -> Visibility{
+        if self.is_bat() {Visibility::Inherited} else {Visibility::Hidden}
+    }
+
+    pub fn get_body_visibility(&self) -> Visibility{
+        if self.is_bat() {Visibility::Hidden} else {Visibility::Inherited}
+    }
+
     pub fn get_item_visibility(&self) -> Visibility{
+        if self.is_bat() {return Visibility::Hidden}
         if self.armed {
             Visibility::Inherited
         } else {
@@ -531,16 +585,12 @@ impl AnimationController{
     }
 
     pub fn get_item_offset(&self) -> Vec3{
-        if self.direction == 1 || self.direction == 3 {
-            self.ticker.get(&self.current_animation.frame_time, &self.current_animation.item_offsets).unwrap_or(Vec3::ZERO)
-            + if self.is_mirrored() {vec3(0., 0., 0.)} else {vec3(0., 0., 0.)}
-        } else {
-            self.ticker.get(&self.current_animation.frame_time, &self.current_animation.item_offsets).unwrap_or(Vec3::ZERO)
-        }
+        self.ticker.get(&self.current_animation.frame_time, &self.current_animation.item_offsets).unwrap_or(Vec3::ZERO)
     }
     
     pub fn tick(&mut self, dt: f32){
         self.ticker.tick(dt * self.animation_speed);
+        if let Some(t) = self.bat.as_mut() {*t += dt}
         if self.ticker.frame > self.current_animation.duration{
             if self.current_animation.looped {
                 self.ticker.frame %= self.current_animation.duration;
@@ -584,10 +634,10 @@ impl CharacterAnimation {
         self
     }
     pub fn player(frame_time: FrameTime, frame_idx: Vec<usize>, item_offsets: Vec<Vec3>) -> Self {
-        if frame_idx.len() == 0 {panic!("Empty animation!")}
+        if frame_idx.is_empty() {panic!("Empty animation!")}
         let duration = match &frame_time{
             FrameTime::Constant(t) => {t * frame_idx.len() as f32}
-            FrameTime::Sequence(s) => {s.into_iter().sum()}
+            FrameTime::Sequence(s) => {s.iter().sum()}
         };
         CharacterAnimation{
             duration,
@@ -600,10 +650,10 @@ impl CharacterAnimation {
         }
     }
     pub fn simple(frame_time: FrameTime, frame_idx: Vec<usize>) -> Self {
-        if frame_idx.len() == 0 {panic!("Empty animation!")}
+        if frame_idx.is_empty() {panic!("Empty animation!")}
         let duration = match &frame_time{
             FrameTime::Constant(t) => {t * frame_idx.len() as f32}
-            FrameTime::Sequence(s) => {s.into_iter().sum()}
+            FrameTime::Sequence(s) => {s.iter().sum()}
         };
         CharacterAnimation{
             duration,
@@ -617,10 +667,10 @@ impl CharacterAnimation {
         }
     }
     pub fn civilian(frame_time: FrameTime, frame_idx: Vec<usize>, parts_offsets: Vec<Vec3>) -> Self {
-        if frame_idx.len() == 0 {panic!("Empty animation!")}
+        if frame_idx.is_empty() {panic!("Empty animation!")}
         let duration = match &frame_time{
             FrameTime::Constant(t) => {t * frame_idx.len() as f32}
-            FrameTime::Sequence(s) => {s.into_iter().sum()}
+            FrameTime::Sequence(s) => {s.iter().sum()}
         };
         CharacterAnimation{
             duration,
@@ -637,13 +687,12 @@ impl CharacterAnimation {
 }
 
 pub(super) fn update_sprites(
-    mut commands: Commands,
-    mut player_controllers: Query<(Entity, &mut AnimationController, &Children)>,
+    mut player_controllers: Query<(&mut AnimationController, &Children)>,
     mut sprites: Query<(&mut Sprite, &mut TextureAtlas, &PartType, &mut Transform, &mut Visibility)>,
     time: Res<Time>,
 ){
     let dt = time.delta_seconds();
-    for (e, mut c, children) in player_controllers.iter_mut(){
+    for (mut c, children) in player_controllers.iter_mut(){
         c.tick(dt);
         let mirrored = c.is_mirrored();
         let offset = c.get_parts_offset();
@@ -654,6 +703,15 @@ pub(super) fn update_sprites(
                         transform.translation = sprite_type.default_offset();
                         atlas.index = c.get_idx();
                         sprite.flip_x = mirrored;
+                        *visibility = c.get_body_visibility();
+                    },
+                    PartType::Bat => {
+                        // a little bob on top of the wing flaps
+                        let bob = (c.bat.unwrap_or(0.) * 8.).sin();
+                        transform.translation = sprite_type.default_offset() + vec3(0., 2. + bob, 0.);
+                        atlas.index = c.get_bat_idx();
+                        sprite.flip_x = mirrored;
+                        *visibility = c.get_bat_visibility();
                     },
                     PartType::Umbrella => {
                         transform.translation = sprite_type.default_offset() + offset + match c.direction {
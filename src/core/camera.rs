@@ -1,11 +1,9 @@
 pub mod plugin{
 
-use std::{cell::RefCell, default, sync::Mutex};
-
-use bevy::{core_pipeline::{bloom::{BloomCompositeMode, BloomPrefilterSettings, BloomSettings}, motion_blur::{MotionBlur, MotionBlurBundle}, tonemapping::{DebandDither, Tonemapping}}, input::mouse::MouseWheel, math::{vec2, vec3}, prelude::*, render::camera::ScalingMode, window::PrimaryWindow};
+use bevy::{core_pipeline::{bloom::{BloomCompositeMode, BloomPrefilterSettings, BloomSettings}, tonemapping::{DebandDither, Tonemapping}}, math::vec2, prelude::*, render::camera::ScalingMode};
 use bevy_light_2d::light::AmbientLight2d;
 
-use crate::core::{functions::ExpDecay, post_processing::PostProcessUniform};
+use crate::core::{functions::ExpDecay, tunables::Tunables};
 pub struct EnhancedCameraPlugin;
 
 
@@ -13,7 +11,7 @@ pub struct EnhancedCameraPlugin;
 impl Plugin for EnhancedCameraPlugin{
     fn build(&self, app: &mut App) {
         app.add_systems(PreStartup, setup_camera);
-        app.add_systems(PostUpdate, update_camera);
+        app.add_systems(PostUpdate, (apply_camera_tunables, update_camera));
     }
 }
 
@@ -36,21 +34,9 @@ pub struct CameraController{
     pub corners: Option<(Vec2, Vec2)>
 }
 
-
-
-impl CameraController{
-    fn set_scale(&mut self, new_scale: f32, translation_speed: Option<f32>){
-        self.scale = new_scale;
-        self.scale_translation_speed = translation_speed;
-    }
-}
-
-pub const TARGET_WIDTH : f32 = 400.;
-pub const TARGET_HEIGHT : f32 = 300.;
-pub const TARGET_ASPECT : f32 = TARGET_WIDTH / TARGET_HEIGHT;
-
 fn setup_camera(
     mut commands: Commands,
+    tunables: Res<Tunables>,
 ){
     commands.spawn((
         Camera2dBundle{
@@ -59,10 +45,7 @@ fn setup_camera(
                 ..default()
             },
             projection: OrthographicProjection {
-                scaling_mode: ScalingMode::AutoMin {
-                    min_width: TARGET_WIDTH,
-                    min_height: TARGET_HEIGHT,
-                },
+                scaling_mode: scaling_mode(&tunables),
                 near: -800.,
                 far: 800.,
                 ..default()
@@ -92,6 +75,23 @@ fn setup_camera(
     ));
 }
 
+fn scaling_mode(tunables: &Tunables) -> ScalingMode {
+    ScalingMode::AutoMin {
+        min_width: tunables.camera_width,
+        min_height: tunables.camera_height,
+    }
+}
+
+fn apply_camera_tunables(
+    tunables: Res<Tunables>,
+    mut projection: Query<&mut OrthographicProjection, With<MainCamera>>,
+) {
+    if !tunables.is_changed() {return}
+    for mut projection in projection.iter_mut() {
+        projection.scaling_mode = scaling_mode(&tunables);
+    }
+}
+
 struct CameraScale(f32);
 
 impl Default for CameraScale{
@@ -107,8 +107,7 @@ fn update_camera(
     mut cameras_q: Query<(&mut Transform, &GlobalTransform, &mut CameraController, &Camera)>,
     targets_q: Query<(&Transform, &CameraFollow), (With<CameraFollow>, Without<CameraController>)>,
     time: Res<Time>,
-    mut evr_scroll: EventReader<MouseWheel>,
-    mut camera_scale: Local<CameraScale>,
+    camera_scale: Local<CameraScale>,
 ){
     let mut follow_position = Vec3::ZERO;
     let mut highest = 0;
@@ -135,8 +134,8 @@ fn update_camera(
             let up = corners.0.y.max(corners.1.y);
             let down = corners.0.y.min(corners.1.y);
             let world_screen_size = camera.logical_viewport_size().unwrap();
-            let Some(a) = camera.viewport_to_world(&glob, vec2(0., 0.)).map(|ray| ray.origin.truncate()) else {continue;};
-            let Some(b) = camera.viewport_to_world(&glob, world_screen_size).map(|ray| ray.origin.truncate()) else {continue;};
+            let Some(a) = camera.viewport_to_world(glob, vec2(0., 0.)).map(|ray| ray.origin.truncate()) else {continue;};
+            let Some(b) = camera.viewport_to_world(glob, world_screen_size).map(|ray| ray.origin.truncate()) else {continue;};
             let world_screen_size = (b - a) * vec2(0.5, -0.5);
             let size = (corners.0 - corners.1).abs();
             let center = (corners.0 + corners.1) / 2.;
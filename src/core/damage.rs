@@ -0,0 +1,230 @@
+use bevy::prelude::*;
+use bevy::app::Plugin;
+use bevy_rapier2d::prelude::Velocity;
+use serde::{Deserialize, Serialize};
+
+use crate::systems::GameState;
+
+use super::functions::ExpDecay;
+
+/// No resistance goes above this, so nothing becomes immune.
+pub const MAX_RESISTANCE: f32 = 0.75;
+const KNOCKBACK_DECAY: f32 = 10.;
+const FLASH_RATE: f32 = 12.;
+
+pub struct DamagePlugin;
+
+impl Plugin for DamagePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<DamageEvent>()
+            .add_event::<DamageTaken>()
+            .add_systems(Update, apply_damage.in_set(DamageSystem).run_if(in_state(GameState::Playing)))
+            .add_systems(PostUpdate, (tick_invulnerability, apply_knockback)
+                .run_if(in_state(GameState::Playing))
+                .before(bevy_rapier2d::plugin::PhysicsSet::SyncBackend));
+        // a respawn may take it off mid blink
+        app.world_mut().register_component_hooks::<Invulnerable>().on_remove(|mut world, entity, _| {
+            let mut stack = vec![entity];
+            while let Some(e) = stack.pop() {
+                if let Some(mut sprite) = world.get_mut::<Sprite>(e) {
+                    sprite.color.set_alpha(1.);
+                }
+                if let Some(children) = world.get::<Children>(e) {
+                    stack.extend(children.iter().copied());
+                }
+            }
+        });
+    }
+}
+
+/// Turns [`DamageEvent`]s into [`DamageTaken`], reactions go after it.
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct DamageSystem;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum DamageKind {
+    /// Thrown forks, knives, garlic and stakes.
+    Projectile,
+    /// Civilian punches.
+    Melee,
+    /// Touching a hunter's cross in daylight.
+    Holy,
+    /// The player dashing through a hunter at night.
+    Bite,
+}
+
+impl DamageKind {
+    pub const ALL: [DamageKind; 4] = [DamageKind::Projectile, DamageKind::Melee, DamageKind::Holy, DamageKind::Bite];
+}
+
+#[derive(Event, Clone, Debug)]
+pub struct DamageEvent {
+    pub target: Entity,
+    pub source: Option<Entity>,
+    pub kind: DamageKind,
+    pub amount: f32,
+    /// Velocity added to the target, fades out over a few frames.
+    pub knockback: Vec2,
+}
+
+/// What a [`DamageEvent`] did after resistances and i-frames, sent once per landed hit.
+#[derive(Event, Clone, Debug)]
+pub struct DamageTaken {
+    pub target: Entity,
+    pub source: Option<Entity>,
+    pub kind: DamageKind,
+    pub amount: f32,
+    pub killed: bool,
+}
+
+#[derive(Component, Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct Health {
+    pub current: f32,
+    pub max: f32,
+}
+
+impl Health {
+    pub fn new(max: f32) -> Self {
+        Health {current: max, max}
+    }
+
+    pub fn heal(&mut self, amount: f32) {
+        self.current = (self.current + amount).clamp(0., self.max);
+    }
+
+    pub fn fraction(&self) -> f32 {
+        (self.current / self.max).clamp(0., 1.)
+    }
+
+    pub fn is_dead(&self) -> bool {
+        self.current <= 0.
+    }
+}
+
+/// Lets [`DamageEvent`]s reach the [`Health`] of this entity.
+#[derive(Component, Clone, Copy, Debug)]
+pub struct Damageable {
+    pub resistances: Resistances,
+    /// Seconds of [`Invulnerable`] after each hit, 0 for none.
+    pub invulnerability: f32,
+}
+
+/// Fraction of each damage kind that is ignored.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Resistances {
+    pub projectile: f32,
+    pub melee: f32,
+    pub holy: f32,
+    pub bite: f32,
+}
+
+impl Default for Resistances {
+    fn default() -> Self {
+        Resistances {projectile: 0.2, melee: 0.2, holy: 0.2, bite: 0.}
+    }
+}
+
+impl Resistances {
+    pub fn none() -> Self {
+        Resistances {projectile: 0., melee: 0., holy: 0., bite: 0.}
+    }
+
+    pub fn get(&self, kind: DamageKind) -> f32 {
+        match kind {
+            DamageKind::Projectile => self.projectile,
+            DamageKind::Melee => self.melee,
+            DamageKind::Holy => self.holy,
+            DamageKind::Bite => self.bite,
+        }.clamp(0., MAX_RESISTANCE)
+    }
+
+    pub fn reduce(&self, kind: DamageKind, amount: f32) -> f32 {
+        amount * (1. - self.get(kind))
+    }
+}
+
+/// Hits are ignored until the timer runs out, the sprites blink meanwhile.
+#[derive(Component)]
+pub struct Invulnerable {
+    pub timer: Timer,
+}
+
+impl Invulnerable {
+    pub fn new(secs: f32) -> Self {
+        Invulnerable {timer: Timer::from_seconds(secs, TimerMode::Once)}
+    }
+}
+
+#[derive(Component, Default)]
+pub struct Knockback {
+    pub v: Vec2,
+}
+
+/// A target takes one hit per i-frame window, even from a stream of hits in the same frame.
+/// Dead targets take none.
+fn apply_damage(
+    mut commands: Commands,
+    mut events: EventReader<DamageEvent>,
+    mut targets: Query<(&mut Health, &Damageable, Has<Invulnerable>)>,
+    mut taken: EventWriter<DamageTaken>,
+    mut hit_this_frame: Local<Vec<Entity>>,
+) {
+    hit_this_frame.clear();
+    for hit in events.read() {
+        let Ok((mut health, damageable, invulnerable)) = targets.get_mut(hit.target) else {continue};
+        if invulnerable || health.is_dead() {continue}
+        if damageable.invulnerability > 0. && hit_this_frame.contains(&hit.target) {continue}
+        let amount = damageable.resistances.reduce(hit.kind, hit.amount);
+        health.current -= amount;
+        let mut target = commands.entity(hit.target);
+        target.insert(Knockback {v: hit.knockback});
+        if damageable.invulnerability > 0. {
+            target.insert(Invulnerable::new(damageable.invulnerability));
+            hit_this_frame.push(hit.target);
+        }
+        taken.send(DamageTaken {
+            target: hit.target,
+            source: hit.source,
+            kind: hit.kind,
+            amount,
+            killed: health.is_dead(),
+        });
+    }
+}
+
+fn tick_invulnerability(
+    mut commands: Commands,
+    mut invulnerable: Query<(Entity, &mut Invulnerable)>,
+    children: Query<&Children>,
+    mut sprites: Query<&mut Sprite>,
+    time: Res<Time>,
+) {
+    for (entity, mut invulnerable) in invulnerable.iter_mut() {
+        invulnerable.timer.tick(time.delta());
+        let finished = invulnerable.timer.finished();
+        let alpha = if finished || (invulnerable.timer.elapsed_secs() * FLASH_RATE) as i32 % 2 == 1 {1.} else {0.3};
+        for child in children.iter_descendants(entity) {
+            if let Ok(mut sprite) = sprites.get_mut(child) {
+                sprite.color.set_alpha(alpha);
+            }
+        }
+        if finished {
+            commands.entity(entity).remove::<Invulnerable>();
+        }
+    }
+}
+
+/// Runs after the controllers wrote their velocity, so the push isn't overwritten.
+fn apply_knockback(
+    mut knocked: Query<(&mut Knockback, &mut Velocity)>,
+    time: Res<Time>,
+) {
+    let dt = time.delta_seconds();
+    for (mut knockback, mut velocity) in knocked.iter_mut() {
+        if knockback.v == Vec2::ZERO {continue}
+        velocity.linvel += knockback.v;
+        knockback.v = knockback.v.exp_decay(Vec2::ZERO, KNOCKBACK_DECAY, dt);
+        if knockback.v.length() < 1. {knockback.v = Vec2::ZERO}
+    }
+}
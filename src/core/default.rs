@@ -1,20 +1,22 @@
 pub mod plugin {
-    use bevy::{app::Plugin, math::vec2, prelude::{default, App, PluginGroup}, render::{texture::ImagePlugin, view::Msaa}, window::{PresentMode, Window, WindowPlugin, WindowTheme}, DefaultPlugins};
+    use bevy::{app::Plugin, math::vec2, prelude::{default, App, PluginGroup}, render::{texture::ImagePlugin, view::Msaa}, window::{Window, WindowPlugin, WindowTheme}, DefaultPlugins};
     use bevy_easings::EasingsPlugin;
-    use bevy_rapier2d::render::RapierDebugRenderPlugin;
+    use bevy_light_2d::prelude::Light2dPlugin;
     use bevy_rapier2d::prelude::*;
 
-    use crate::core::{camera::plugin::EnhancedCameraPlugin, despawn_lifetime::DespawnLifetimePlugin, functions::TextureAtlasLayoutHandles, post_processing::PostProcessPlugin, ui::UIPlugin};
+    use crate::core::{camera::plugin::EnhancedCameraPlugin, despawn_lifetime::DespawnLifetimePlugin, functions::TextureAtlasLayoutHandles, input::ActionPlugin, post_processing::PostProcessPlugin, rng::GameRngPlugin, settings::{Settings, SettingsPlugin}, ui::UIPlugin};
     pub struct DefaultPlugin;
 
     impl Plugin for DefaultPlugin {
         fn build(&self, app: &mut App) {
+            let settings = Settings::load();
             app.add_plugins((
                 DefaultPlugins
                 .set(ImagePlugin::default_nearest())
                 .set(WindowPlugin {
                     primary_window: Some(Window {
-                                present_mode: PresentMode::AutoNoVsync,
+                                present_mode: settings.present_mode(),
+                                mode: settings.window_mode,
                                 window_theme: Some(WindowTheme::Dark),
                                 title: "Bloody Night".into(),
                                 ..default()
@@ -26,26 +28,35 @@ pub mod plugin {
                 //SwitchableRapierDebugPlugin,
                 EnhancedCameraPlugin,
                 PostProcessPlugin,
+                Light2dPlugin,
                 DespawnLifetimePlugin,
                 EasingsPlugin,
-                UIPlugin
+                UIPlugin,
+                GameRngPlugin,
             ),
             );
+            app.insert_resource(settings);
+            app.add_plugins((SettingsPlugin, ActionPlugin));
             app.insert_resource(TextureAtlasLayoutHandles::default());
             app.insert_resource(Msaa::Off);
-            app.insert_resource(RapierConfiguration {
-                gravity: vec2(0.0, 0.0),
-                physics_pipeline_active: true,
-                query_pipeline_active: true,
-                timestep_mode: TimestepMode::Variable {
-                    max_dt: 1.0 / 60.0,
-                    time_scale: 1.0,
-                    substeps: 1,
-                },
-                scaled_shape_subdivision: 10,
-                force_update_from_transform_changes: false,
-            });
-            app.add_plugins(bevy::diagnostic::FrameTimeDiagnosticsPlugin::default());
+            app.insert_resource(rapier_configuration());
+            app.add_plugins(bevy::diagnostic::FrameTimeDiagnosticsPlugin);
+        }
+    }
+
+    /// Top down world: no gravity, one physics step per frame.
+    pub fn rapier_configuration() -> RapierConfiguration {
+        RapierConfiguration {
+            gravity: vec2(0.0, 0.0),
+            physics_pipeline_active: true,
+            query_pipeline_active: true,
+            timestep_mode: TimestepMode::Variable {
+                max_dt: 1.0 / 60.0,
+                time_scale: 1.0,
+                substeps: 1,
+            },
+            scaled_shape_subdivision: 10,
+            force_update_from_transform_changes: false,
         }
     }
 }
@@ -1,3 +1,5 @@
+use std::path::PathBuf;
+
 use bevy::{prelude::*, utils::HashMap};
 
 pub trait ExpDecay<T> {
@@ -22,6 +24,16 @@ impl ExpDecay<Vec2> for Vec2 {
     }
 }
 
+/// OnExit helper for screens: removes every root tagged with `T`.
+pub fn despawn_with<T: Component>(
+    mut commands: Commands,
+    query: Query<Entity, With<T>>,
+) {
+    for e in query.iter() {
+        commands.entity(e).despawn_recursive();
+    }
+}
+
 #[derive(Resource, Default)]
 pub struct TextureAtlasLayoutHandles{
     handles: HashMap<String, Handle<TextureAtlasLayout>>
@@ -37,4 +49,32 @@ impl TextureAtlasLayoutHandles {
             handle
         }
     }
-}
\ No newline at end of file
+}
+
+/// Whether `--name` was passed, with or without a value
+pub fn has_arg(name: &str) -> bool {
+    let flag = format!("--{}", name);
+    std::env::args().skip(1).any(|arg| arg == flag || arg.starts_with(&format!("{}=", flag)))
+}
+
+/// Value of a `--name value` or `--name=value` command line argument,
+/// a following `--other` flag isn't taken as the value
+pub fn arg_value(name: &str) -> Option<String> {
+    let flag = format!("--{}", name);
+    let mut args = std::env::args().skip(1).peekable();
+    while let Some(arg) = args.next() {
+        if arg == flag {
+            return args.next_if(|v| !v.starts_with("--"));
+        }
+        if let Some(v) = arg.strip_prefix(&flag).and_then(|v| v.strip_prefix('=')) {
+            return Some(v.to_string());
+        }
+    }
+    None
+}
+
+/// `file` in the game's data dir, where saves, the leaderboard and the run history live.
+/// No data dir on the web, so none of them are stored there.
+pub fn data_path(file: &str) -> Option<PathBuf> {
+    Some(dirs::data_dir()?.join("bloody_night").join(file))
+}
@@ -0,0 +1,181 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+use bevy::app::{Plugin, PluginsState};
+use bevy::input::InputPlugin;
+use bevy::log::LogPlugin;
+use bevy::render::{render_resource::Shader, texture::ImagePlugin};
+use bevy::state::app::StatesPlugin;
+use bevy::time::TimeUpdateStrategy;
+use bevy_rapier2d::prelude::*;
+
+use crate::{
+    map::plugin::TrespassableCells,
+    npc::{components::{Civilian, Hunter, NpcState}, systems::RosesCollected},
+    player::components::Player,
+    systems::GameState,
+    GamePlugin,
+};
+
+use super::{damage::Health, default::plugin::rapier_configuration, despawn_lifetime::DespawnLifetimePlugin, functions::TextureAtlasLayoutHandles, replay::{Replay, ReplayPlugin}, input::{Action, ActionPlugin, InjectedActions}, rng::{GameRng, GameRngPlugin}, settings::Settings};
+
+/// Fixed frame delta of the simulation, so a seed always plays out the same way.
+pub const HEADLESS_DT: f32 = 1. / 60.;
+pub const DEFAULT_FRAMES: usize = 3600;
+const MAP_LOAD_FRAMES: usize = 600;
+
+/// Window-less counterpart of DefaultPlugin: no renderer, camera, ui or audio.
+pub struct HeadlessPlugin;
+
+impl Plugin for HeadlessPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins((
+            MinimalPlugins,
+            LogPlugin::default(),
+            TransformPlugin,
+            HierarchyPlugin,
+            InputPlugin,
+            StatesPlugin,
+            AssetPlugin::default(),
+            ImagePlugin::default_nearest(),
+            RapierPhysicsPlugin::<NoUserData>::default(),
+            DespawnLifetimePlugin,
+        ));
+        // Normally registered by the render plugins, but the game hands out handles to them
+        app.init_asset::<Shader>()
+            .init_asset::<TextureAtlasLayout>()
+            .init_asset::<Font>();
+        // LDtk sets it from the level background
+        app.init_resource::<ClearColor>();
+        // Defaults, not the player's file, so runs stay comparable
+        app.init_resource::<Settings>();
+        app.add_plugins(ActionPlugin);
+        app.insert_resource(TextureAtlasLayoutHandles::default());
+        app.insert_resource(rapier_configuration());
+        app.insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_secs_f32(HEADLESS_DT)));
+    }
+}
+
+/// The whole game without a window, stepped frame by frame.
+pub struct Simulation {
+    pub app: App,
+}
+
+impl Simulation {
+    /// Nothing is read from the command line, so the same seed always plays out the same run.
+    pub fn new(seed: u64) -> Self {
+        let mut app = App::new();
+        app.insert_resource(GameRng::new(seed));
+        app.insert_resource(Replay::Off);
+        Self::build(app)
+    }
+
+    /// Seed comes from `--seed` (or `--replay`) like in the windowed game.
+    pub fn from_args() -> Self {
+        let mut app = App::new();
+        app.add_plugins(GameRngPlugin);
+        Self::build(app)
+    }
+
+    fn build(mut app: App) -> Self {
+        app.add_plugins((HeadlessPlugin, ReplayPlugin, GamePlugin));
+        while app.plugins_state() == PluginsState::Adding {
+            #[cfg(not(target_arch = "wasm32"))]
+            bevy::tasks::tick_global_task_pools_on_main_thread();
+        }
+        app.finish();
+        app.cleanup();
+        Simulation { app }
+    }
+
+    pub fn step(&mut self, frames: usize) {
+        for _ in 0..frames {
+            self.app.update();
+        }
+    }
+
+    /// Steps until the level is spawned and the pathfinding grid is built, then starts the run.
+    /// Loading takes a varying number of frames, so the run must not be ticking meanwhile.
+    pub fn wait_for_map(&mut self) -> bool {
+        for _ in 0..MAP_LOAD_FRAMES {
+            if self.trespassable().ready {break}
+            self.app.update();
+        }
+        if !self.trespassable().ready {return false}
+        // No one to press Start
+        self.app.world_mut().resource_mut::<NextState<GameState>>().set(GameState::Playing);
+        self.app.update();
+        true
+    }
+
+    /// Holds `action` until [`Simulation::release`], as if a button was down.
+    pub fn press(&mut self, action: Action) {
+        self.app.world_mut().resource_mut::<InjectedActions>().press(action);
+    }
+
+    pub fn release(&mut self, action: Action) {
+        self.app.world_mut().resource_mut::<InjectedActions>().release(action);
+    }
+
+    /// Analog movement, `None` hands it back to the (absent) hardware.
+    pub fn set_move_axis(&mut self, axis: Option<Vec2>) {
+        self.app.world_mut().resource_mut::<InjectedActions>().set_axis(axis);
+    }
+
+    pub fn world(&self) -> &World {
+        self.app.world()
+    }
+
+    pub fn trespassable(&self) -> &TrespassableCells {
+        self.world().resource::<TrespassableCells>()
+    }
+
+    pub fn roses(&self) -> &RosesCollected {
+        self.world().resource::<RosesCollected>()
+    }
+
+    pub fn player(&mut self) -> Option<&Player> {
+        let world = self.app.world_mut();
+        let mut q = world.query::<&Player>();
+        q.get_single(world).ok()
+    }
+
+    pub fn player_health(&mut self) -> Option<Health> {
+        let world = self.app.world_mut();
+        let mut q = world.query_filtered::<&Health, With<Player>>();
+        q.get_single(world).ok().copied()
+    }
+
+    pub fn count<T: Component>(&mut self) -> usize {
+        let world = self.app.world_mut();
+        world.query_filtered::<(), With<T>>().iter(world).count()
+    }
+
+    pub fn npc_states(&mut self) -> Vec<NpcState> {
+        let world = self.app.world_mut();
+        world.query::<&NpcState>().iter(world).copied().collect()
+    }
+}
+
+/// `--headless 3600` plays that many frames without a window and logs the outcome, a bare `--headless` plays [`DEFAULT_FRAMES`].
+pub fn run_headless(frames: usize) {
+    let mut sim = Simulation::from_args();
+    if !sim.wait_for_map() {
+        error!("Map did not load in {} frames", MAP_LOAD_FRAMES);
+        return;
+    }
+    sim.step(frames);
+    let civilians = sim.count::<Civilian>();
+    let hunters = sim.count::<Hunter>();
+    let chasing = sim.npc_states().iter().filter(|s| matches!(s, NpcState::Chase | NpcState::Attack)).count();
+    let roses = sim.roses().collected;
+    let seed = sim.world().resource::<GameRng>().seed();
+    let hp = sim.player_health().map(|h| h.current).unwrap_or(0.);
+    match sim.player() {
+        Some(player) => info!(
+            "Seed {} after {} frames: hp {:.1}, xp {:.1}, score {:.0}, roses {}, civilians {}, hunters {} ({} chasing)",
+            seed, frames, hp, player.xp, player.score, roses, civilians, hunters, chasing
+        ),
+        None => info!("Seed {} after {} frames: no player", seed, frames),
+    }
+}
@@ -0,0 +1,338 @@
+use std::collections::BTreeMap;
+
+use bevy::prelude::*;
+use bevy::app::Plugin;
+use bevy::input::InputSystem;
+use bevy::math::vec2;
+use bevy::utils::HashSet;
+use serde::{Deserialize, Serialize};
+
+use super::settings::Settings;
+
+/// Turns keyboard, mouse and gamepad state into [`Action`]s using the bindings from [`Settings`].
+pub struct ActionPlugin;
+
+impl Plugin for ActionPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ActionState>()
+            .init_resource::<InjectedActions>()
+            .init_resource::<Rebinding>()
+            .add_systems(PreUpdate, (capture_rebinding, update_action_state).chain().in_set(ActionSystem).after(InputSystem));
+    }
+}
+
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ActionSystem;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum Action {
+    MoveUp,
+    MoveDown,
+    MoveLeft,
+    MoveRight,
+    Dash,
+    Ability1,
+    Ability2,
+    Bite,
+    Pause,
+    Interact,
+    ToggleStaticTrees,
+    /// Analog movement, from the left stick or the four direction actions.
+    /// Has no bindings of its own, so it is not in [`Action::ALL`].
+    MoveAxis,
+}
+
+impl Action {
+    pub const ALL: [Action; 11] = [
+        Action::MoveUp,
+        Action::MoveDown,
+        Action::MoveLeft,
+        Action::MoveRight,
+        Action::Dash,
+        Action::Ability1,
+        Action::Ability2,
+        Action::Bite,
+        Action::Pause,
+        Action::Interact,
+        Action::ToggleStaticTrees,
+    ];
+
+    /// Actions that ability slots can be bound to.
+    pub const ABILITY_SLOTS: [Action; 3] = [Action::Dash, Action::Ability1, Action::Ability2];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Action::MoveUp => "Up",
+            Action::MoveDown => "Down",
+            Action::MoveLeft => "Left",
+            Action::MoveRight => "Right",
+            Action::Dash => "Dash",
+            Action::Ability1 => "Ability 1",
+            Action::Ability2 => "Ability 2",
+            Action::Bite => "Bite",
+            Action::Pause => "Pause",
+            Action::Interact => "Interact",
+            Action::ToggleStaticTrees => "Static Trees",
+            Action::MoveAxis => "Move",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Binding {
+    Key(KeyCode),
+    Mouse(MouseButton),
+    Gamepad(GamepadButtonType),
+}
+
+impl Binding {
+    fn same_device(&self, other: &Binding) -> bool {
+        std::mem::discriminant(self) == std::mem::discriminant(other)
+    }
+
+    pub fn name(&self) -> String {
+        match self {
+            Binding::Key(key) => format!("{:?}", key),
+            Binding::Mouse(button) => format!("Mouse {:?}", button),
+            Binding::Gamepad(button) => format!("Pad {:?}", button),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Bindings(pub BTreeMap<Action, Vec<Binding>>);
+
+impl Default for Bindings {
+    fn default() -> Self {
+        use Binding::*;
+        Bindings(BTreeMap::from([
+            (Action::MoveUp, vec![Key(KeyCode::KeyW), Key(KeyCode::ArrowUp), Gamepad(GamepadButtonType::DPadUp)]),
+            (Action::MoveDown, vec![Key(KeyCode::KeyS), Key(KeyCode::ArrowDown), Gamepad(GamepadButtonType::DPadDown)]),
+            (Action::MoveLeft, vec![Key(KeyCode::KeyA), Key(KeyCode::ArrowLeft), Gamepad(GamepadButtonType::DPadLeft)]),
+            (Action::MoveRight, vec![Key(KeyCode::KeyD), Key(KeyCode::ArrowRight), Gamepad(GamepadButtonType::DPadRight)]),
+            (Action::Dash, vec![Key(KeyCode::ShiftLeft), Mouse(MouseButton::Right), Gamepad(GamepadButtonType::East), Gamepad(GamepadButtonType::RightTrigger2)]),
+            (Action::Ability1, vec![Key(KeyCode::KeyQ), Gamepad(GamepadButtonType::West)]),
+            (Action::Ability2, vec![Key(KeyCode::KeyF), Gamepad(GamepadButtonType::North)]),
+            (Action::Bite, vec![Key(KeyCode::KeyR), Mouse(MouseButton::Left), Gamepad(GamepadButtonType::RightTrigger)]),
+            (Action::Pause, vec![Key(KeyCode::Escape), Gamepad(GamepadButtonType::Start)]),
+            (Action::Interact, vec![Key(KeyCode::KeyE), Key(KeyCode::Enter), Key(KeyCode::Space), Gamepad(GamepadButtonType::South)]),
+            (Action::ToggleStaticTrees, vec![Key(KeyCode::F4)]),
+        ]))
+    }
+}
+
+impl Bindings {
+    /// Actions missing from an older settings file keep their default bindings.
+    pub fn get(&self, action: Action) -> Vec<Binding> {
+        match self.0.get(&action) {
+            Some(bindings) => bindings.clone(),
+            None => Bindings::default().0.remove(&action).unwrap_or_default(),
+        }
+    }
+
+    /// Replaces the bindings of `action` from the same device as `binding`.
+    pub fn rebind(&mut self, action: Action, binding: Binding) {
+        let mut bindings = self.get(action);
+        bindings.retain(|b| !b.same_device(&binding));
+        bindings.insert(0, binding);
+        self.0.insert(action, bindings);
+    }
+
+    pub fn label(&self, action: Action) -> String {
+        // the first binding of each device is enough for a button
+        let mut shown: Vec<Binding> = vec![];
+        for b in self.get(action) {
+            if !shown.iter().any(|s| s.same_device(&b)) {shown.push(b)}
+        }
+        let names: Vec<String> = shown.iter().map(|b| b.name()).collect();
+        format!("{}: {}", action.name(), names.join(" / "))
+    }
+}
+
+#[derive(Resource, Default)]
+pub struct ActionState {
+    pressed: HashSet<Action>,
+    just_pressed: HashSet<Action>,
+    move_axis: Vec2,
+}
+
+impl ActionState {
+    pub fn pressed(&self, action: Action) -> bool {
+        self.pressed.contains(&action)
+    }
+
+    pub fn just_pressed(&self, action: Action) -> bool {
+        self.just_pressed.contains(&action)
+    }
+
+    /// Up to length 1 for a full stick tilt, zero for button actions.
+    pub fn axis(&self, action: Action) -> Vec2 {
+        match action {
+            Action::MoveAxis => self.move_axis,
+            _ => Vec2::ZERO,
+        }
+    }
+}
+
+/// Actions held by code instead of hardware, for tests and the headless simulation.
+#[derive(Resource, Default)]
+pub struct InjectedActions {
+    held: HashSet<Action>,
+    axis: Option<Vec2>,
+}
+
+impl InjectedActions {
+    pub fn press(&mut self, action: Action) {
+        self.held.insert(action);
+    }
+
+    pub fn release(&mut self, action: Action) {
+        self.held.remove(&action);
+    }
+
+    /// Overrides the move axis like an analog stick would, `None` gives it back.
+    pub fn set_axis(&mut self, axis: Option<Vec2>) {
+        self.axis = axis;
+    }
+
+    pub fn clear(&mut self) {
+        *self = InjectedActions::default();
+    }
+}
+
+/// Action waiting for a key on the settings screen.
+#[derive(Resource, Default)]
+pub struct Rebinding(pub Option<Action>);
+
+const STICK_DEADZONE: f32 = 0.2;
+const STICK_PRESS: f32 = 0.5;
+
+fn update_action_state(
+    mut state: ResMut<ActionState>,
+    injected: Res<InjectedActions>,
+    rebinding: Res<Rebinding>,
+    settings: Res<Settings>,
+    keyboard: Res<ButtonInput<KeyCode>>,
+    mouse: Res<ButtonInput<MouseButton>>,
+    gamepads: Res<Gamepads>,
+    gamepad_buttons: Res<ButtonInput<GamepadButton>>,
+    gamepad_axes: Res<Axis<GamepadAxis>>,
+) {
+    let mut stick = Vec2::ZERO;
+    for gamepad in gamepads.iter() {
+        let x = gamepad_axes.get(GamepadAxis::new(gamepad, GamepadAxisType::LeftStickX)).unwrap_or(0.);
+        let y = gamepad_axes.get(GamepadAxis::new(gamepad, GamepadAxisType::LeftStickY)).unwrap_or(0.);
+        let v = vec2(x, y);
+        if v.length() > STICK_DEADZONE && v.length() > stick.length() {stick = v}
+    }
+
+    let mut pressed = HashSet::new();
+    for action in Action::ALL {
+        let down = settings.bindings.get(action).iter().any(|binding| match binding {
+            Binding::Key(key) => keyboard.pressed(*key),
+            Binding::Mouse(button) => mouse.pressed(*button),
+            Binding::Gamepad(button) => gamepads.iter().any(|g| gamepad_buttons.pressed(GamepadButton::new(g, *button))),
+        });
+        if down {pressed.insert(action);}
+    }
+    if stick.y > STICK_PRESS {pressed.insert(Action::MoveUp);}
+    if stick.y < -STICK_PRESS {pressed.insert(Action::MoveDown);}
+    if stick.x < -STICK_PRESS {pressed.insert(Action::MoveLeft);}
+    if stick.x > STICK_PRESS {pressed.insert(Action::MoveRight);}
+    pressed.extend(injected.held.iter().copied());
+
+    let digital = vec2(
+        pressed.contains(&Action::MoveRight) as i32 as f32 - pressed.contains(&Action::MoveLeft) as i32 as f32,
+        pressed.contains(&Action::MoveUp) as i32 as f32 - pressed.contains(&Action::MoveDown) as i32 as f32,
+    ).clamp_length_max(1.);
+    state.move_axis = if let Some(axis) = injected.axis {
+        axis.clamp_length_max(1.)
+    } else if stick.length() > digital.length() {
+        stick.clamp_length_max(1.)
+    } else {
+        digital
+    };
+    if state.move_axis != Vec2::ZERO {pressed.insert(Action::MoveAxis);}
+    // the key that picks a new binding must not also trigger its action
+    let swallow = rebinding.0.is_some() || (rebinding.is_changed() && !rebinding.is_added());
+    state.just_pressed = if swallow {
+        HashSet::new()
+    } else {
+        pressed.difference(&state.pressed).copied().collect()
+    };
+    state.pressed = pressed;
+}
+
+/// The next key, mouse or gamepad button becomes the binding, Escape cancels.
+fn capture_rebinding(
+    mut rebinding: ResMut<Rebinding>,
+    mut settings: ResMut<Settings>,
+    keyboard: Res<ButtonInput<KeyCode>>,
+    mouse: Res<ButtonInput<MouseButton>>,
+    gamepad_buttons: Res<ButtonInput<GamepadButton>>,
+) {
+    let Some(action) = rebinding.0 else {return};
+    if keyboard.just_pressed(KeyCode::Escape) {
+        rebinding.0 = None;
+        return;
+    }
+    let binding = keyboard.get_just_pressed().next().map(|k| Binding::Key(*k))
+        .or_else(|| mouse.get_just_pressed().next().map(|b| Binding::Mouse(*b)))
+        .or_else(|| gamepad_buttons.get_just_pressed().next().map(|b| Binding::Gamepad(b.button_type)));
+    if let Some(binding) = binding {
+        settings.bindings.rebind(action, binding);
+        rebinding.0 = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::input::InputPlugin;
+
+    use super::*;
+
+    fn app() -> App {
+        let mut app = App::new();
+        app.add_plugins((InputPlugin, ActionPlugin)).init_resource::<Settings>();
+        app
+    }
+
+    #[test]
+    fn injected_actions_resolve() {
+        let mut app = app();
+        app.world_mut().resource_mut::<InjectedActions>().press(Action::Dash);
+        app.world_mut().resource_mut::<InjectedActions>().press(Action::MoveRight);
+        app.update();
+        let state = app.world().resource::<ActionState>();
+        assert!(state.pressed(Action::Dash));
+        assert!(state.just_pressed(Action::Dash));
+        assert!(!state.pressed(Action::Bite));
+        assert!(state.pressed(Action::MoveAxis));
+        assert_eq!(state.axis(Action::MoveAxis), vec2(1., 0.));
+
+        app.update();
+        let state = app.world().resource::<ActionState>();
+        assert!(state.pressed(Action::Dash));
+        assert!(!state.just_pressed(Action::Dash));
+
+        app.world_mut().resource_mut::<InjectedActions>().release(Action::Dash);
+        app.update();
+        assert!(!app.world().resource::<ActionState>().pressed(Action::Dash));
+    }
+
+    #[test]
+    fn injected_axis_overrides_directions() {
+        let mut app = app();
+        let mut injected = app.world_mut().resource_mut::<InjectedActions>();
+        injected.press(Action::MoveUp);
+        injected.set_axis(Some(vec2(3., 4.)));
+        app.update();
+        assert!(app.world().resource::<ActionState>().axis(Action::MoveAxis).abs_diff_eq(vec2(0.6, 0.8), 1e-6));
+
+        app.world_mut().resource_mut::<InjectedActions>().clear();
+        app.update();
+        let state = app.world().resource::<ActionState>();
+        assert_eq!(state.axis(Action::MoveAxis), Vec2::ZERO);
+        assert!(!state.pressed(Action::MoveAxis));
+    }
+}
@@ -4,4 +4,14 @@ pub mod camera;
 pub mod functions;
 pub mod post_processing;
 pub mod ui;
-pub mod despawn_lifetime;
\ No newline at end of file
+pub mod despawn_lifetime;
+pub mod rng;
+pub mod replay;
+pub mod headless;
+pub mod run;
+pub mod settings;
+pub mod input;
+pub mod ron_asset;
+pub mod tunables;
+pub mod damage;
+pub mod save;
@@ -5,6 +5,8 @@
 //! To adapt this example for 2D, replace all instances of 3D structures (such as `Core3D`, etc.) with their corresponding 2D counterparts.
 //!
 //! This is a fairly low level example and assumes some familiarity with rendering concepts and wgpu.
+// The ShaderType derive generates per field size checks that are never called
+#![allow(dead_code)]
 use bevy::{
     core_pipeline::{
         core_2d::graph::{Core2d, Node2d},
@@ -18,11 +20,11 @@ use bevy::{
         }, render_resource::{
             binding_types::{sampler, texture_2d, uniform_buffer},
             *,
-        }, renderer::{RenderContext, RenderDevice}, texture::BevyDefault, view::ViewTarget, RenderApp
+        }, renderer::{RenderContext, RenderDevice}, view::ViewTarget, RenderApp
     }, window::WindowResized
 };
 
-use super::camera::plugin::MainCamera;
+use super::{camera::plugin::MainCamera, settings::Settings};
 
 /// This example uses a shader source file from the assets subdirectory
 const SHADER_ASSET_PATH: &str = "shaders/post_processing.wgsl";
@@ -356,11 +358,14 @@ fn update_settings(
     mut settings: Query<&mut PostProcessUniform>, 
     camera: Query<&GlobalTransform, With<MainCamera>>,
     time: Res<Time>,    
+    user_settings: Res<Settings>,
 ) {
     let mut settings = settings.single_mut();
     let camera_transform = camera.single().translation().xy();
     settings.translation = camera_transform;
     settings.time = time.elapsed_seconds();
+    settings.vignette_strength = user_settings.vignette_strength;
+    settings.wave_strength = user_settings.wave_strength;
 }
 
 fn on_resize_system(
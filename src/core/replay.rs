@@ -0,0 +1,156 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+use bevy::app::{AppExit, Plugin};
+use bevy::time::{TimeSystem, TimeUpdateStrategy};
+use serde::{Deserialize, Serialize};
+
+use crate::player::components::{KillPlayer, PlayerInput};
+
+use super::{functions::arg_value, rng::GameRng};
+
+const REPLAY_VERSION: u32 = 1;
+/// The recording is also flushed every this many frames, so a crash loses at most a few seconds of it.
+const FLUSH_FRAMES: usize = 600;
+
+/// `--record run.ron` writes every frame of the run into a replay file,
+/// `--replay run.ron` plays one back with the same seed, frame deltas and input.
+pub struct ReplayPlugin;
+
+impl Plugin for ReplayPlugin {
+    fn build(&self, app: &mut App) {
+        // already set up by the headless simulation, which leaves the command line alone
+        if app.world().contains_resource::<Replay>() {
+            Self::add_systems(app);
+            return;
+        }
+        let replay = if let Some(path) = arg_value("replay") {
+            match load_replay(&path) {
+                Ok(file) => {
+                    info!("Playing replay {} ({} frames, seed {})", path, file.frames.len(), file.seed);
+                    app.world_mut().resource_mut::<GameRng>().reseed(file.seed);
+                    Replay::Playing { file, frame: 0 }
+                }
+                Err(e) => {
+                    error!("Failed to load replay {}: {}", path, e);
+                    Replay::Off
+                }
+            }
+        } else if let Some(path) = arg_value("record") {
+            let seed = app.world().resource::<GameRng>().seed();
+            info!("Recording replay to {}", path);
+            Replay::Recording { path, file: ReplayFile { version: REPLAY_VERSION, seed, frames: vec![] } }
+        } else {
+            Replay::Off
+        };
+        app.insert_resource(replay);
+        Self::add_systems(app);
+    }
+}
+
+impl ReplayPlugin {
+    fn add_systems(app: &mut App) {
+        app.add_systems(First, set_replay_delta.before(TimeSystem));
+        app.add_systems(Last, (advance_replay, save_recording).chain());
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ReplayFile {
+    pub version: u32,
+    pub seed: u64,
+    pub frames: Vec<ReplayFrame>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ReplayFrame {
+    pub dt: Duration,
+    pub input: PlayerInput,
+}
+
+#[derive(Resource)]
+pub enum Replay {
+    Off,
+    Recording { path: String, file: ReplayFile },
+    Playing { file: ReplayFile, frame: usize },
+}
+
+pub fn is_replaying(replay: Res<Replay>) -> bool {
+    matches!(*replay, Replay::Playing { .. })
+}
+
+fn load_replay(path: &str) -> Result<ReplayFile, String> {
+    let data = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+    let file: ReplayFile = ron::from_str(&data).map_err(|e| e.to_string())?;
+    if file.version != REPLAY_VERSION {
+        return Err(format!("unsupported replay version {}", file.version));
+    }
+    Ok(file)
+}
+
+fn set_replay_delta(
+    mut replay: ResMut<Replay>,
+    mut strategy: ResMut<TimeUpdateStrategy>,
+) {
+    let dt = match replay.as_ref() {
+        Replay::Playing { file, frame } => file.frames.get(*frame).map(|recorded| recorded.dt),
+        _ => return,
+    };
+    if let Some(dt) = dt {
+        *strategy = TimeUpdateStrategy::ManualDuration(dt);
+    } else {
+        info!("Replay finished, handing control back to the keyboard");
+        *strategy = TimeUpdateStrategy::Automatic;
+        *replay = Replay::Off;
+    }
+}
+
+/// Runs in PreUpdate after the keyboard is read, so the recorded input wins.
+pub fn feed_replay_input(
+    replay: Res<Replay>,
+    mut input: ResMut<PlayerInput>,
+) {
+    let Replay::Playing { file, frame } = replay.as_ref() else {return};
+    if let Some(recorded) = file.frames.get(*frame) {
+        *input = recorded.input.clone();
+    }
+}
+
+fn advance_replay(
+    mut replay: ResMut<Replay>,
+    input: Res<PlayerInput>,
+    time: Res<Time<Real>>,
+) {
+    match replay.as_mut() {
+        Replay::Off => {},
+        Replay::Recording { file, .. } => {
+            file.frames.push(ReplayFrame { dt: time.delta(), input: input.clone() });
+        },
+        Replay::Playing { frame, .. } => {
+            *frame += 1;
+        },
+    }
+}
+
+/// Flushes the recording on every death, on exit and every [`FLUSH_FRAMES`] frames,
+/// so a bug report always has the fatal frames, even when the game panics or is killed.
+fn save_recording(
+    replay: Res<Replay>,
+    mut kill_player: EventReader<KillPlayer>,
+    mut exit: EventReader<AppExit>,
+) {
+    let died = kill_player.read().count() > 0;
+    let exiting = exit.read().count() > 0;
+    let Replay::Recording { path, file } = replay.as_ref() else {return};
+    let periodic = file.frames.len() % FLUSH_FRAMES == 0;
+    if !died && !exiting && !periodic {return}
+    let data = match ron::to_string(file) {
+        Ok(data) => data,
+        Err(e) => {error!("Failed to serialize replay: {}", e); return},
+    };
+    if let Err(e) = std::fs::write(path, data) {
+        error!("Failed to write replay {}: {}", path, e);
+    } else if died || exiting {
+        info!("Replay saved to {} ({} frames)", path, file.frames.len());
+    }
+}
@@ -0,0 +1,119 @@
+use bevy::prelude::*;
+use bevy::app::Plugin;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use serde::{Deserialize, Serialize};
+
+use super::functions::arg_value;
+
+pub struct GameRngPlugin;
+
+impl Plugin for GameRngPlugin {
+    fn build(&self, app: &mut App) {
+        let seed = seed_from_args().unwrap_or_else(|| rand::thread_rng().gen());
+        info!("Run seed: {}", seed);
+        app.insert_resource(GameRng::new(seed));
+    }
+}
+
+/// Named sub-streams of the run rng.
+/// Every stream is its own ChaCha stream of the same seed, so pulling numbers from one
+/// (e.g. a sound variant) never shifts the others (e.g. npc decisions).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum RngStream {
+    Ai,
+    Cosmetic,
+    Spawning,
+    Audio,
+    Upgrades,
+}
+
+/// Serializable, so a saved run continues with the same numbers.
+#[derive(Resource, Clone, Debug, Serialize, Deserialize)]
+pub struct GameRng {
+    seed: u64,
+    ai: ChaCha8Rng,
+    cosmetic: ChaCha8Rng,
+    spawning: ChaCha8Rng,
+    audio: ChaCha8Rng,
+    upgrades: ChaCha8Rng,
+}
+
+impl GameRng {
+    pub fn new(seed: u64) -> Self {
+        let stream = |id: RngStream| {
+            let mut rng = ChaCha8Rng::seed_from_u64(seed);
+            rng.set_stream(id as u64);
+            rng
+        };
+        GameRng {
+            seed,
+            ai: stream(RngStream::Ai),
+            cosmetic: stream(RngStream::Cosmetic),
+            spawning: stream(RngStream::Spawning),
+            audio: stream(RngStream::Audio),
+            upgrades: stream(RngStream::Upgrades),
+        }
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Restarts every stream from the beginning of `seed`.
+    pub fn reseed(&mut self, seed: u64) {
+        *self = GameRng::new(seed);
+    }
+
+    pub fn stream(&mut self, stream: RngStream) -> &mut ChaCha8Rng {
+        match stream {
+            RngStream::Ai => &mut self.ai,
+            RngStream::Cosmetic => &mut self.cosmetic,
+            RngStream::Spawning => &mut self.spawning,
+            RngStream::Audio => &mut self.audio,
+            RngStream::Upgrades => &mut self.upgrades,
+        }
+    }
+
+    pub fn ai(&mut self) -> &mut ChaCha8Rng {
+        &mut self.ai
+    }
+
+    pub fn cosmetic(&mut self) -> &mut ChaCha8Rng {
+        &mut self.cosmetic
+    }
+
+    pub fn spawning(&mut self) -> &mut ChaCha8Rng {
+        &mut self.spawning
+    }
+
+    pub fn audio(&mut self) -> &mut ChaCha8Rng {
+        &mut self.audio
+    }
+
+    pub fn upgrades(&mut self) -> &mut ChaCha8Rng {
+        &mut self.upgrades
+    }
+
+    /// Independent rng derived from `stream`, for code that can't hold the resource
+    /// (parallel iteration, locals that live across frames).
+    pub fn fork(&mut self, stream: RngStream) -> ChaCha8Rng {
+        ChaCha8Rng::seed_from_u64(self.stream(stream).gen())
+    }
+}
+
+/// A restarted run starts every stream over, so it plays out like a fresh start with the same seed.
+pub fn reseed_rng(mut rng: ResMut<GameRng>) {
+    let seed = rng.seed();
+    rng.reseed(seed);
+}
+
+/// `--seed 1234` or `--seed=1234`
+fn seed_from_args() -> Option<u64> {
+    let v = arg_value("seed")?;
+    let seed = v.parse::<u64>().ok();
+    if seed.is_none() {
+        warn!("Invalid seed {:?}, using a random one", v);
+    }
+    seed
+}
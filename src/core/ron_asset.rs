@@ -0,0 +1,115 @@
+use std::{fmt, marker::PhantomData};
+
+use bevy::prelude::*;
+use bevy::app::Plugin;
+use bevy::asset::{io::Reader, AssetLoader, AsyncReadExt, LoadContext};
+use serde::de::DeserializeOwned;
+
+/// Data files that are loaded as an asset and mirrored into a resource of the same type.
+pub trait RonAsset: Asset + Resource + Clone + PartialEq + Default + DeserializeOwned {}
+
+impl<A: Asset + Resource + Clone + PartialEq + Default + DeserializeOwned> RonAsset for A {}
+
+/// Loads `path` from `assets/` and copies it into the `A` resource every time the file
+/// is (re)loaded. Until then the resource holds `A::default()`, a broken file keeps the
+/// last good values.
+pub struct RonAssetPlugin<A> {
+    path: &'static str,
+    extension: &'static str,
+    _marker: PhantomData<fn() -> A>,
+}
+
+impl<A> RonAssetPlugin<A> {
+    /// `extension` is the double extension the loader claims, e.g. `tunables.ron`.
+    pub fn new(path: &'static str, extension: &'static str) -> Self {
+        RonAssetPlugin {path, extension, _marker: PhantomData}
+    }
+}
+
+impl<A: RonAsset> Plugin for RonAssetPlugin<A> {
+    fn build(&self, app: &mut App) {
+        let path = self.path;
+        app.init_asset::<A>()
+            .register_asset_loader(RonAssetLoader::<A> {extensions: [self.extension], _marker: PhantomData})
+            .init_resource::<A>()
+            .add_systems(Startup, move |mut commands: Commands, asset_server: Res<AssetServer>| {
+                commands.insert_resource(RonAssetHandle::<A>(asset_server.load(path)));
+            })
+            .add_systems(PreUpdate, mirror_ron_asset::<A>);
+    }
+}
+
+#[derive(Resource)]
+pub struct RonAssetHandle<A: Asset>(pub Handle<A>);
+
+struct RonAssetLoader<A> {
+    extensions: [&'static str; 1],
+    _marker: PhantomData<fn() -> A>,
+}
+
+#[derive(Debug)]
+pub enum RonAssetError {
+    Io(std::io::Error),
+    Ron(ron::error::SpannedError),
+}
+
+impl fmt::Display for RonAssetError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RonAssetError::Io(e) => write!(f, "could not read file: {}", e),
+            RonAssetError::Ron(e) => write!(f, "invalid ron: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for RonAssetError {}
+
+impl From<std::io::Error> for RonAssetError {
+    fn from(e: std::io::Error) -> Self {
+        RonAssetError::Io(e)
+    }
+}
+
+impl From<ron::error::SpannedError> for RonAssetError {
+    fn from(e: ron::error::SpannedError) -> Self {
+        RonAssetError::Ron(e)
+    }
+}
+
+impl<A: RonAsset> AssetLoader for RonAssetLoader<A> {
+    type Asset = A;
+    type Settings = ();
+    type Error = RonAssetError;
+
+    async fn load<'a>(
+        &'a self,
+        reader: &'a mut Reader<'_>,
+        _settings: &'a (),
+        _load_context: &'a mut LoadContext<'_>,
+    ) -> Result<A, RonAssetError> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        Ok(ron::de::from_bytes(&bytes)?)
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &self.extensions
+    }
+}
+
+fn mirror_ron_asset<A: RonAsset>(
+    mut events: EventReader<AssetEvent<A>>,
+    handle: Option<Res<RonAssetHandle<A>>>,
+    assets: Res<Assets<A>>,
+    mut resource: ResMut<A>,
+) {
+    let Some(handle) = handle else {return};
+    for event in events.read() {
+        if !event.is_loaded_with_dependencies(&handle.0) && !event.is_modified(&handle.0) {continue}
+        let Some(loaded) = assets.get(&handle.0) else {continue};
+        if *resource != *loaded {
+            info!("Reloaded {}", A::short_type_path());
+            *resource = loaded.clone();
+        }
+    }
+}
@@ -0,0 +1,79 @@
+use bevy::prelude::*;
+use bevy::app::Plugin;
+use bevy::ecs::system::SystemId;
+
+/// Ends the current run and starts a fresh one from the registered initial state.
+#[derive(Event)]
+pub struct RestartRun;
+
+pub struct RunPlugin;
+
+impl Plugin for RunPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<RestartRun>();
+        app.world_mut().get_resource_or_insert_with(RunRegistry::default);
+        app.add_systems(PostUpdate, restart_run.run_if(on_event::<RestartRun>()));
+    }
+}
+
+/// Everything that belongs to a single run. Plugins register their own parts
+/// through [`RunScopedApp`], so a restart never misses a late addition.
+#[derive(Resource, Default)]
+pub struct RunRegistry {
+    teardown: Vec<Box<dyn Fn(&mut World) + Send + Sync>>,
+    setup: Vec<SystemId>,
+}
+
+pub trait RunScopedApp {
+    /// Inserts `initial` and puts it back on every restart.
+    fn run_scoped_resource<R: Resource + Clone>(&mut self, initial: R) -> &mut Self;
+    /// Despawns every entity with `C` on restart.
+    fn run_scoped_entities<C: Component>(&mut self) -> &mut Self;
+    /// Runs `system` once on restart, after the teardown.
+    fn on_run_restart<M>(&mut self, system: impl IntoSystem<(), (), M> + 'static) -> &mut Self;
+}
+
+impl RunScopedApp for App {
+    fn run_scoped_resource<R: Resource + Clone>(&mut self, initial: R) -> &mut Self {
+        self.insert_resource(initial.clone());
+        self.world_mut().get_resource_or_insert_with(RunRegistry::default).teardown.push(Box::new(move |world| {
+            world.insert_resource(initial.clone());
+        }));
+        self
+    }
+
+    fn run_scoped_entities<C: Component>(&mut self) -> &mut Self {
+        self.world_mut().get_resource_or_insert_with(RunRegistry::default).teardown.push(Box::new(despawn_all::<C>));
+        self
+    }
+
+    fn on_run_restart<M>(&mut self, system: impl IntoSystem<(), (), M> + 'static) -> &mut Self {
+        let id = self.world_mut().register_system(system);
+        self.world_mut().get_resource_or_insert_with(RunRegistry::default).setup.push(id);
+        self
+    }
+}
+
+fn despawn_all<C: Component>(world: &mut World) {
+    let entities: Vec<Entity> = world.query_filtered::<Entity, With<C>>().iter(world).collect();
+    for e in entities {
+        // may already be gone as a child of an earlier one
+        if let Some(e) = world.get_entity_mut(e) {
+            e.despawn_recursive();
+        }
+    }
+}
+
+fn restart_run(world: &mut World) {
+    info!("Restarting run");
+    world.resource_scope(|world, registry: Mut<RunRegistry>| {
+        for teardown in registry.teardown.iter() {
+            teardown(world);
+        }
+        for id in registry.setup.iter() {
+            if let Err(e) = world.run_system(*id) {
+                error!("Run setup system failed: {}", e);
+            }
+        }
+    });
+}
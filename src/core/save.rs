@@ -0,0 +1,265 @@
+use std::path::PathBuf;
+
+use bevy::prelude::*;
+use bevy::app::Plugin;
+use bevy::window::WindowCloseRequested;
+use bevy_ecs_ldtk::EntityIid;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    map::plugin::{CollectableRose, CollectableRoseSpawner, RemainingRoses},
+    npc::{components::{Civilian, Hunter, NpcPath, NpcState}, director::Director, heat::Heat, systems::{spawn_civilian, spawn_hunter, RosesCollected}},
+    player::{abilities::Abilities, archetype::SelectedArchetype, coffin::CoffinCheckpoint, components::{KillPlayer, MenuChoice, Player, PlayerInput, UpgradeButton}, stats::RunStats, upgrades::{TakenUpgrades, UpgradeOffer}},
+    sounds::components::PlaySoundEvent,
+    systems::{GameState, RunClock},
+};
+
+use super::{damage::{Damageable, Health, Resistances}, functions::{data_path, TextureAtlasLayoutHandles}, rng::GameRng, tunables::Tunables};
+
+/// Bump when the layout of [`RunSave`] changes, older files are then ignored.
+pub const SAVE_VERSION: u32 = 1;
+
+/// Writes the run in progress on [`SaveRun`] or when the window is closed, and
+/// restores it when the start screen's continue button is used.
+/// A save is used up by continuing it or by dying.
+pub struct SavePlugin;
+
+impl Plugin for SavePlugin {
+    fn build(&self, app: &mut App) {
+        let mid_run = in_state(GameState::Playing)
+            .or_else(in_state(GameState::Paused))
+            .or_else(in_state(GameState::LevelUp))
+            .or_else(in_state(GameState::Settings));
+        app.add_event::<SaveRun>()
+            .add_systems(Update, continue_run.run_if(in_state(GameState::MainMenu)))
+            .add_systems(OnEnter(GameState::Playing), restore_run.run_if(resource_exists::<PendingRestore>))
+            .add_systems(PostUpdate, (
+                save_run.run_if(mid_run.and_then(on_event::<SaveRun>().or_else(on_event::<WindowCloseRequested>()))),
+                discard_save.run_if(on_event::<KillPlayer>()),
+            ));
+    }
+}
+
+/// Writes the current run to disk, handled in `PostUpdate` so it still happens on the frame the app exits.
+#[derive(Event)]
+pub struct SaveRun;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RunSave {
+    pub version: u32,
+    pub archetype: usize,
+    pub player: Player,
+    pub position: Vec2,
+    pub health: Health,
+    pub resistances: Resistances,
+    pub abilities: Abilities,
+    pub roses: RosesCollected,
+    /// `EntityIid`s of the rose spawners whose rose is still there.
+    pub remaining_roses: Vec<String>,
+    pub clock: f32,
+    pub civilians: Vec<NpcSave>,
+    pub hunters: Vec<NpcSave>,
+    pub upgrades: Vec<UpgradeButton>,
+    pub rerolls_used: u32,
+    pub stats: RunStats,
+    pub checkpoint: CoffinCheckpoint,
+    pub heat: Heat,
+    /// The night's hunter budget isn't refilled by continuing.
+    pub director: Director,
+    pub rng: GameRng,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct NpcSave {
+    pub position: Vec2,
+    pub state: NpcState,
+    pub path: NpcPath,
+    pub health: Health,
+}
+
+impl RunSave {
+    pub fn exists() -> bool {
+        save_path().is_some_and(|path| path.exists())
+    }
+
+    /// A missing, broken or outdated file all count as no save.
+    pub fn load() -> Option<Self> {
+        let path = save_path()?;
+        let data = std::fs::read_to_string(&path).ok()?;
+        match ron::from_str::<RunSave>(&data) {
+            Ok(save) if save.version == SAVE_VERSION => Some(save),
+            Ok(save) => {
+                warn!("Ignoring save {} of version {}, expected {}", path.display(), save.version, SAVE_VERSION);
+                None
+            }
+            Err(e) => {
+                warn!("Invalid save file {}: {}", path.display(), e);
+                None
+            }
+        }
+    }
+
+    pub fn save(&self) {
+        let Some(path) = save_path() else {return};
+        let data = match ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default()) {
+            Ok(data) => data,
+            Err(e) => {error!("Failed to serialize run: {}", e); return},
+        };
+        if let Some(dir) = path.parent() {
+            let _ = std::fs::create_dir_all(dir);
+        }
+        match std::fs::write(&path, data) {
+            Ok(()) => info!("Saved run to {}", path.display()),
+            Err(e) => error!("Failed to write save {}: {}", path.display(), e),
+        }
+    }
+
+    pub fn delete() {
+        let Some(path) = save_path() else {return};
+        if path.exists() {
+            if let Err(e) = std::fs::remove_file(&path) {
+                error!("Failed to remove save {}: {}", path.display(), e);
+            }
+        }
+    }
+}
+
+fn save_path() -> Option<PathBuf> {
+    data_path("run.ron")
+}
+
+/// The loaded save, applied once the player is back in the world.
+#[derive(Resource)]
+struct PendingRestore(RunSave);
+
+fn save_run(
+    player: Query<(&Player, &Transform, &Health, &Damageable, &Abilities)>,
+    civilians: Query<(&Transform, &NpcState, &NpcPath, &Health), With<Civilian>>,
+    hunters: Query<(&Transform, &NpcState, &NpcPath, &Health), With<Hunter>>,
+    spawners: Query<(&EntityIid, Option<&Children>), With<CollectableRoseSpawner>>,
+    roses: Query<(), With<CollectableRose>>,
+    roses_collected: Res<RosesCollected>,
+    clock: Res<RunClock>,
+    taken: Res<TakenUpgrades>,
+    offer: Res<UpgradeOffer>,
+    selected: Res<SelectedArchetype>,
+    stats: Res<RunStats>,
+    checkpoint: Res<CoffinCheckpoint>,
+    heat: Res<Heat>,
+    director: Res<Director>,
+    rng: Res<GameRng>,
+) {
+    let Ok((player, transform, health, damageable, abilities)) = player.get_single() else {return};
+    if player.is_dead {return}
+    let npcs = |(transform, state, path, health): (&Transform, &NpcState, &NpcPath, &Health)| {
+        (*state != NpcState::Dead).then(|| NpcSave {
+            position: transform.translation.xy(),
+            state: *state,
+            path: path.clone(),
+            health: *health,
+        })
+    };
+    RunSave {
+        version: SAVE_VERSION,
+        archetype: selected.index,
+        player: player.clone(),
+        position: transform.translation.xy(),
+        health: *health,
+        resistances: damageable.resistances,
+        abilities: abilities.clone(),
+        roses: roses_collected.clone(),
+        remaining_roses: spawners.iter()
+            .filter(|(_, children)| children.is_some_and(|c| c.iter().any(|e| roses.contains(*e))))
+            .map(|(iid, _)| iid.as_str().to_string())
+            .collect(),
+        clock: clock.elapsed,
+        civilians: civilians.iter().filter_map(npcs).collect(),
+        hunters: hunters.iter().filter_map(npcs).collect(),
+        upgrades: taken.taken.clone(),
+        rerolls_used: offer.rerolls_used,
+        stats: stats.clone(),
+        checkpoint: checkpoint.clone(),
+        heat: heat.clone(),
+        director: director.clone(),
+        rng: rng.clone(),
+    }.save();
+}
+
+fn discard_save() {
+    RunSave::delete();
+}
+
+/// Loads the save and starts the run, the player picks up the saved archetype on the way.
+fn continue_run(
+    mut commands: Commands,
+    input: Res<PlayerInput>,
+    mut selected: ResMut<SelectedArchetype>,
+    mut next_state: ResMut<NextState<GameState>>,
+    mut play_sound: EventWriter<PlaySoundEvent>,
+) {
+    if input.menu != Some(MenuChoice::Continue) {return}
+    let Some(save) = RunSave::load() else {return};
+    selected.index = save.archetype;
+    commands.insert_resource(PendingRestore(save));
+    play_sound.send(PlaySoundEvent::Selected);
+    next_state.set(GameState::Playing);
+}
+
+/// Respawns the saved npcs through the usual spawn helpers, then puts back their state.
+/// The save is only used up once all of it is applied.
+fn restore_run(
+    mut commands: Commands,
+    pending: Res<PendingRestore>,
+    player: Query<Entity, With<Player>>,
+    npcs: Query<Entity, Or<(With<Civilian>, With<Hunter>)>>,
+    spawners: Query<(&EntityIid, &Children), With<CollectableRoseSpawner>>,
+    roses: Query<(), With<CollectableRose>>,
+    asset_server: Res<AssetServer>,
+    mut layout_handles: ResMut<TextureAtlasLayoutHandles>,
+    mut rng: ResMut<GameRng>,
+    tunables: Res<Tunables>,
+) {
+    let save = &pending.0;
+    commands.remove_resource::<PendingRestore>();
+    if let Ok(entity) = player.get_single() {
+        let mut abilities = save.abilities.clone();
+        abilities.reset();
+        commands.entity(entity).insert((
+            abilities,
+            save.player.clone(),
+            save.health,
+            Damageable {resistances: save.resistances, invulnerability: tunables.invulnerability},
+            Transform::from_translation(save.position.extend(0.)),
+        ));
+    }
+    for entity in npcs.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+    for npc in &save.civilians {
+        let entity = spawn_civilian(&mut commands, &asset_server, npc.position, &mut layout_handles, &mut rng, &tunables);
+        commands.entity(entity).insert((npc.state, npc.path.clone(), npc.health));
+    }
+    for npc in &save.hunters {
+        let entity = spawn_hunter(&mut commands, &asset_server, npc.position, &mut layout_handles, &tunables);
+        commands.entity(entity).insert((npc.state, npc.path.clone(), npc.health));
+    }
+    // spawners the level brings in later leave these out, those already there lose their rose here
+    commands.insert_resource(RemainingRoses(Some(save.remaining_roses.clone())));
+    for (iid, children) in spawners.iter() {
+        if save.remaining_roses.iter().any(|r| r == iid.as_str()) {continue}
+        for child in children.iter().filter(|e| roses.contains(**e)) {
+            commands.entity(*child).despawn_recursive();
+        }
+    }
+    commands.insert_resource(save.roses.clone());
+    commands.insert_resource(RunClock {elapsed: save.clock});
+    commands.insert_resource(TakenUpgrades {taken: save.upgrades.clone()});
+    commands.insert_resource(UpgradeOffer {choices: vec![], rerolls_used: save.rerolls_used});
+    commands.insert_resource(save.stats.clone());
+    commands.insert_resource(save.checkpoint.clone());
+    commands.insert_resource(save.heat.clone());
+    commands.insert_resource(save.director.clone());
+    // last, the respawns above drew from the old stream, so the run goes on as it would have
+    *rng = save.rng.clone();
+    RunSave::delete();
+}
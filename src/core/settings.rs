@@ -0,0 +1,179 @@
+use std::path::PathBuf;
+
+use bevy::prelude::*;
+use bevy::app::Plugin;
+use bevy::window::{PresentMode, PrimaryWindow, WindowMode};
+use serde::{Deserialize, Serialize};
+
+use super::input::{Action, Bindings};
+
+/// Loads the settings before the window exists, so the first frame already
+/// uses the saved window mode. Saves them back whenever they change.
+pub struct SettingsPlugin;
+
+impl Plugin for SettingsPlugin {
+    fn build(&self, app: &mut App) {
+        if !app.world().contains_resource::<Settings>() {
+            app.insert_resource(Settings::load());
+        }
+        app.add_systems(PostUpdate, (apply_window_settings, save_settings));
+    }
+}
+
+#[derive(Resource, Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub day_volume: f32,
+    pub night_volume: f32,
+    pub sfx_volume: f32,
+    pub static_trees: bool,
+    pub vignette_strength: f32,
+    pub wave_strength: f32,
+    pub window_mode: WindowMode,
+    pub vsync: bool,
+    pub bindings: Bindings,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            day_volume: 0.4,
+            night_volume: 1.,
+            sfx_volume: 1.,
+            static_trees: false,
+            vignette_strength: 0.35,
+            wave_strength: 15.,
+            window_mode: WindowMode::Windowed,
+            vsync: false,
+            bindings: Bindings::default(),
+        }
+    }
+}
+
+/// One adjustable line of the settings screen.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SettingEntry {
+    DayVolume,
+    NightVolume,
+    SfxVolume,
+    StaticTrees,
+    Vignette,
+    Waves,
+    WindowMode,
+    Vsync,
+    Bind(Action),
+}
+
+impl SettingEntry {
+    pub fn all() -> Vec<SettingEntry> {
+        let mut entries = vec![
+            SettingEntry::DayVolume,
+            SettingEntry::NightVolume,
+            SettingEntry::SfxVolume,
+            SettingEntry::StaticTrees,
+            SettingEntry::Vignette,
+            SettingEntry::Waves,
+            SettingEntry::WindowMode,
+            SettingEntry::Vsync,
+        ];
+        entries.extend(Action::ALL.map(SettingEntry::Bind));
+        entries
+    }
+}
+
+impl Settings {
+    /// No config dir (web) or a broken file both fall back to the defaults.
+    pub fn load() -> Self {
+        let Some(path) = settings_path() else {return Settings::default()};
+        let Ok(data) = std::fs::read_to_string(&path) else {return Settings::default()};
+        match ron::from_str(&data) {
+            Ok(settings) => settings,
+            Err(e) => {
+                warn!("Invalid settings file {}: {}", path.display(), e);
+                Settings::default()
+            }
+        }
+    }
+
+    pub fn save(&self) {
+        let Some(path) = settings_path() else {return};
+        let data = match ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default()) {
+            Ok(data) => data,
+            Err(e) => {error!("Failed to serialize settings: {}", e); return},
+        };
+        if let Some(dir) = path.parent() {
+            let _ = std::fs::create_dir_all(dir);
+        }
+        if let Err(e) = std::fs::write(&path, data) {
+            error!("Failed to write settings {}: {}", path.display(), e);
+        }
+    }
+
+    pub fn present_mode(&self) -> PresentMode {
+        if self.vsync {PresentMode::AutoVsync} else {PresentMode::AutoNoVsync}
+    }
+
+    pub fn label(&self, entry: SettingEntry) -> String {
+        let on_off = |v: bool| if v {"On"} else {"Off"};
+        match entry {
+            SettingEntry::DayVolume => format!("Day: {:.0}%", self.day_volume * 100.),
+            SettingEntry::NightVolume => format!("Night: {:.0}%", self.night_volume * 100.),
+            SettingEntry::SfxVolume => format!("Sounds: {:.0}%", self.sfx_volume * 100.),
+            SettingEntry::StaticTrees => format!("Static Trees: {}", on_off(self.static_trees)),
+            SettingEntry::Vignette => format!("Vignette: {:.0}%", self.vignette_strength * 100.),
+            SettingEntry::Waves => format!("Waves: {:.0}", self.wave_strength),
+            SettingEntry::WindowMode => format!("Window: {}", match self.window_mode {
+                WindowMode::Windowed => "Windowed",
+                WindowMode::BorderlessFullscreen => "Borderless",
+                _ => "Fullscreen",
+            }),
+            SettingEntry::Vsync => format!("VSync: {}", on_off(self.vsync)),
+            SettingEntry::Bind(action) => self.bindings.label(action),
+        }
+    }
+
+    /// Steps `entry` up (`dir` > 0) or down, wrapping around at the ends.
+    pub fn adjust(&mut self, entry: SettingEntry, dir: i32) {
+        match entry {
+            SettingEntry::DayVolume => step(&mut self.day_volume, dir, 0.1, 1.),
+            SettingEntry::NightVolume => step(&mut self.night_volume, dir, 0.1, 1.),
+            SettingEntry::SfxVolume => step(&mut self.sfx_volume, dir, 0.1, 1.),
+            SettingEntry::StaticTrees => self.static_trees = !self.static_trees,
+            SettingEntry::Vignette => step(&mut self.vignette_strength, dir, 0.05, 1.),
+            SettingEntry::Waves => step(&mut self.wave_strength, dir, 5., 30.),
+            SettingEntry::WindowMode => {
+                let modes = [WindowMode::Windowed, WindowMode::BorderlessFullscreen, WindowMode::Fullscreen];
+                let i = modes.iter().position(|m| *m == self.window_mode).unwrap_or(0) as i32;
+                self.window_mode = modes[(i + dir).rem_euclid(modes.len() as i32) as usize];
+            },
+            SettingEntry::Vsync => self.vsync = !self.vsync,
+            // rebinding waits for a key, see `Rebinding`
+            SettingEntry::Bind(_) => {},
+        }
+    }
+}
+
+fn step(v: &mut f32, dir: i32, step: f32, max: f32) {
+    let next = ((*v + dir.signum() as f32 * step) / step).round() * step;
+    *v = if next > max + step * 0.5 {0.} else if next < -step * 0.5 {max} else {next.clamp(0., max)};
+}
+
+fn settings_path() -> Option<PathBuf> {
+    Some(dirs::config_dir()?.join("bloody_night").join("settings.ron"))
+}
+
+fn apply_window_settings(
+    settings: Res<Settings>,
+    mut window: Query<&mut Window, With<PrimaryWindow>>,
+) {
+    if !settings.is_changed() {return}
+    let Ok(mut window) = window.get_single_mut() else {return};
+    window.mode = settings.window_mode;
+    window.present_mode = settings.present_mode();
+}
+
+fn save_settings(settings: Res<Settings>) {
+    if settings.is_changed() && !settings.is_added() {
+        settings.save();
+    }
+}
@@ -0,0 +1,250 @@
+use bevy::prelude::*;
+use bevy::app::Plugin;
+use serde::{Deserialize, Serialize};
+
+use super::ron_asset::RonAssetPlugin;
+
+pub const TUNABLES_PATH: &str = "game.tunables.ron";
+
+/// Loads the gameplay numbers from `assets/game.tunables.ron` and copies them into
+/// the [`Tunables`] resource every time the file is (re)loaded.
+/// Until the file is loaded the built in defaults are used.
+pub struct TunablesPlugin;
+
+impl Plugin for TunablesPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins(RonAssetPlugin::<Tunables>::new(TUNABLES_PATH, "tunables.ron"));
+    }
+}
+
+#[derive(Asset, TypePath, Resource, Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Tunables {
+    /// Seconds of full day (or full night).
+    pub day_duration: f32,
+    /// Seconds of dusk (or dawn).
+    pub translation_duration: f32,
+
+    pub camera_width: f32,
+    pub camera_height: f32,
+
+    /// Hunters see the player from this far, scaled by its `PlayerVisibility`.
+    pub spot_dist: f32,
+    /// Civilians see the player from this far, scaled by its `PlayerVisibility`.
+    pub spot_dist_civ: f32,
+    /// Hunters closer than this back off, closer than twice this they hold and throw.
+    pub threshold: f32,
+    pub civ_max_speed: f32,
+    pub civ_accel: f32,
+    pub hunter_max_speed: f32,
+    pub hunter_accel: f32,
+    /// Seconds between two hunter throws.
+    pub hunter_throw_cd: f32,
+    pub proj_v: f32,
+
+    /// Chance of a spawner to spawn when its timer finishes.
+    pub spawn_chance: f64,
+    pub max_civilians: usize,
+    pub max_hunters: usize,
+
+    /// Hunters the spawners bring in on the first night.
+    pub hunter_budget: u32,
+    /// Hunters added to the budget every night after.
+    pub hunter_budget_per_night: u32,
+    /// Difficulty added by every night survived.
+    pub difficulty_per_night: f32,
+    /// Difficulty added by every player level.
+    pub difficulty_per_level: f32,
+    pub difficulty_cap: f32,
+    /// Hunter speed gained per point of difficulty, 0.3 is 30% faster at 1.
+    pub hunter_speed_scale: f32,
+    /// Projectile speed gained per point of difficulty.
+    pub proj_v_scale: f32,
+    /// Throw rate gained per point of difficulty.
+    pub throw_rate_scale: f32,
+    /// Hunter sight range gained per point of difficulty.
+    pub sight_scale: f32,
+    /// Seconds for the damage taken to be mostly forgotten.
+    pub damage_memory: f32,
+    /// Share of the max health taken lately that eases the hunters off the most.
+    pub relief_damage: f32,
+    /// Seconds from one kill to the next to keep a streak going.
+    pub streak_window: f32,
+    /// Pace added by every kill of a streak.
+    pub streak_tension: f32,
+
+    /// Heat from one tier to the next.
+    pub heat_per_tier: f32,
+    /// Heat from a kill some npc saw.
+    pub heat_witnessed_kill: f32,
+    /// Heat from a corpse found by an npc.
+    pub heat_corpse: f32,
+    /// Heat lost per second by day.
+    pub heat_decay: f32,
+    /// Chance per heat tier for a night hunter to come in chasing the player.
+    pub heat_chase_chance: f64,
+    /// Hunters kept out by day per heat tier.
+    pub heat_patrol_size: usize,
+
+    pub projectile_damage: f32,
+    pub projectile_knockback: f32,
+    pub civilian_damage: f32,
+    pub civilian_knockback: f32,
+    pub hunter_damage: f32,
+    pub hunter_knockback: f32,
+    /// Seconds the player can't be hurt after a hit.
+    pub invulnerability: f32,
+
+    pub civilian_health: f32,
+    pub hunter_health: f32,
+    /// Damage of the player dashing through a hunter at night.
+    pub bite_damage: f32,
+    pub bite_knockback: f32,
+    /// Seconds an npc can't be hurt after a hit.
+    pub npc_invulnerability: f32,
+
+    /// How visible the player is in the dark, away from any light. 1 is broad daylight.
+    pub dark_visibility: f32,
+    /// Visibility added by a light at full intensity, right under it.
+    pub light_visibility: f32,
+    /// Visibility added by running at full speed.
+    pub move_visibility: f32,
+    /// Visibility added while dashing.
+    pub dash_visibility: f32,
+
+    /// Health lost per second in open sunlight at noon.
+    pub sun_damage: f32,
+    /// Part of the sunlight the umbrella keeps off, 1 is full shade.
+    pub umbrella_shade: f32,
+
+    /// How close a civilian has to be to be bitten.
+    pub feed_range: f32,
+    /// Seconds to drain a civilian, the hp and xp flow in over that time.
+    pub feed_duration: f32,
+    /// Hunters this close to a civilian who saw a feed, or to a hunter calling, come to look.
+    pub alarm_radius: f32,
+    /// Hunters further than `alarm_radius` still hear a calling hunter they can see from this far.
+    pub squad_sight_range: f32,
+    /// Seconds between a hunter's calls while it sees the player.
+    pub squad_shout_cd: f32,
+    /// Cells from the sighting that flanking hunters aim for.
+    pub flank_distance: f32,
+
+    /// Seconds the bat form lasts at most.
+    pub bat_duration: f32,
+    /// Health spent to turn into a bat.
+    pub bat_cost: f32,
+    /// Health lost per second as a bat, on top of the hunger.
+    pub bat_drain: f32,
+    /// Speed multiplier as a bat.
+    pub bat_speed: f32,
+    pub bat_cooldown: f32,
+
+    /// How close to a coffin the player has to be to rest in it.
+    pub coffin_range: f32,
+    /// How much faster the world runs while the player sleeps through the day.
+    pub sleep_speed: f32,
+    /// Part of the xp lost when coming back at a coffin after dying.
+    pub coffin_xp_cost: f32,
+    /// Part of the score lost when coming back at a coffin after dying.
+    pub coffin_score_cost: f32,
+
+    /// Upgrades offered on each level up.
+    pub upgrade_choices: usize,
+    /// Rerolls of the offered upgrades per run.
+    pub upgrade_rerolls: u32,
+}
+
+impl Default for Tunables {
+    fn default() -> Self {
+        Tunables {
+            day_duration: 15.,
+            translation_duration: 1.,
+            camera_width: 400.,
+            camera_height: 300.,
+            spot_dist: 200.,
+            spot_dist_civ: 100.,
+            threshold: 100.,
+            civ_max_speed: 40.,
+            civ_accel: 350.,
+            hunter_max_speed: 50.,
+            hunter_accel: 450.,
+            hunter_throw_cd: 0.5,
+            proj_v: 150.,
+            spawn_chance: 0.15,
+            max_civilians: 200,
+            max_hunters: 200,
+            hunter_budget: 8,
+            hunter_budget_per_night: 4,
+            difficulty_per_night: 0.2,
+            difficulty_per_level: 0.05,
+            difficulty_cap: 2.,
+            hunter_speed_scale: 0.25,
+            proj_v_scale: 0.3,
+            throw_rate_scale: 0.5,
+            sight_scale: 0.2,
+            damage_memory: 10.,
+            relief_damage: 0.5,
+            streak_window: 8.,
+            streak_tension: 0.1,
+            heat_per_tier: 10.,
+            heat_witnessed_kill: 4.,
+            heat_corpse: 2.,
+            heat_decay: 0.5,
+            heat_chase_chance: 0.15,
+            heat_patrol_size: 2,
+            projectile_damage: 8.,
+            projectile_knockback: 60.,
+            civilian_damage: 4.,
+            civilian_knockback: 80.,
+            hunter_damage: 15.,
+            hunter_knockback: 120.,
+            invulnerability: 0.5,
+            civilian_health: 40.,
+            hunter_health: 150.,
+            bite_damage: 100.,
+            bite_knockback: 80.,
+            npc_invulnerability: 0.3,
+            dark_visibility: 0.35,
+            light_visibility: 1.,
+            move_visibility: 0.25,
+            dash_visibility: 0.4,
+            sun_damage: 3.,
+            umbrella_shade: 0.6,
+            feed_range: 20.,
+            feed_duration: 2.5,
+            alarm_radius: 250.,
+            squad_sight_range: 450.,
+            squad_shout_cd: 1.,
+            flank_distance: 4.,
+            bat_duration: 4.,
+            bat_cost: 5.,
+            bat_drain: 4.,
+            bat_speed: 1.6,
+            bat_cooldown: 10.,
+            coffin_range: 20.,
+            sleep_speed: 8.,
+            coffin_xp_cost: 0.5,
+            coffin_score_cost: 0.1,
+            upgrade_choices: 3,
+            upgrade_rerolls: 2,
+        }
+    }
+}
+
+impl Tunables {
+    /// Length of a whole night + dawn + day + dusk.
+    pub fn cycle_duration(&self) -> f32 {
+        (self.day_duration + self.translation_duration) * 2.
+    }
+
+    /// Nights that ended within `elapsed` seconds of a run, runs start at night.
+    pub fn nights_survived(&self, elapsed: f32) -> u32 {
+        let half = self.cycle_duration() * 0.5;
+        ((elapsed + half) / self.cycle_duration()).floor() as u32
+    }
+
+    pub fn camera_aspect(&self) -> f32 {
+        self.camera_width / self.camera_height
+    }
+}
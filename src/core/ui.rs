@@ -1,28 +1,19 @@
-use std::f32::consts::PI;
-
-use bevy::math::{uvec2, vec2};
+use bevy::math::uvec2;
 use bevy::prelude::*;
 use bevy::app::Plugin;
 use bevy::ui::widget::UiImageSize;
-use bevy::ui::ContentSize;
 use bevy::window::WindowResized;
 
 use crate::player::components::Player;
-use crate::{get_local_time_f, DAY_DURATION, TRANSLATION_DURATION};
-
-use super::camera::plugin::{MainCamera, TARGET_ASPECT, TARGET_HEIGHT, TARGET_WIDTH};
-use super::functions::TextureAtlasLayoutHandles;
+use crate::npc::heat::{Heat, HEAT_TIERS};
+use crate::player::sun::SunExposure;
+use crate::{get_local_time_f, RunClock};
 
+use super::tunables::Tunables;
+use super::damage::Health;
 
 
 
-#[derive(Resource)]
-pub struct PlayerUI{
-    hp: f32,
-    xp: f32,
-    time: f32,
-    hunger: f32
-}
 
 pub struct UIPlugin;
 
@@ -44,6 +35,14 @@ pub struct Daynight;
 #[derive(Component)]
 pub struct Blood;
 
+/// Shows while the sun burns the player, redder the stronger it is.
+#[derive(Component)]
+pub struct SunIndicator;
+
+/// The heat tier, redder the more the village is out hunting.
+#[derive(Component)]
+pub struct HeatIndicator;
+
 
 fn setup(
     mut commands: Commands,
@@ -132,23 +131,58 @@ fn setup(
                 ));
             });
 
+    commands.spawn((
+        ImageBundle {
+            style: Style{
+                position_type: PositionType::Absolute,
+                top: Val::Px(8.),
+                right: Val::Px(8.),
+                ..default()
+            },
+            image: UiImage::new(asset_server.load("ui/sun.png")),
+            visibility: Visibility::Hidden,
+            ..default()
+        },
+        PlayerUINode,
+        SunIndicator,
+    ));
+
+    commands.spawn((
+        TextBundle {
+            text: Text::from_section(HEAT_TIERS[0], TextStyle {
+                font: asset_server.load("fonts/Monocraft.ttf"),
+                font_size: 16.,
+                color: Color::WHITE,
+            }),
+            style: Style{
+                position_type: PositionType::Absolute,
+                top: Val::Px(8.),
+                left: Val::Px(8.),
+                ..default()
+            },
+            ..default()
+        },
+        HeatIndicator,
+    ));
+
         
 }
 
 fn on_resize_system(
     mut resize_reader: EventReader<WindowResized>,
     mut ui_style: Query<(&mut Style, &UiImageSize), With<PlayerUINode>>,
+    tunables: Res<Tunables>,
 ) {
     for e in resize_reader.read() {
         for (mut style, size) in ui_style.iter_mut() {
             let size = size.size();
-            if TARGET_ASPECT > e.width / e.height {
+            if tunables.camera_aspect() > e.width / e.height {
                 // width is smaller than now, resize relative to width
-                style.width = Val::Px(2. * e.width / TARGET_WIDTH * size.x as f32);
-                style.height = Val::Px(2. * e.width / TARGET_WIDTH * size.y as f32);
+                style.width = Val::Px(2. * e.width / tunables.camera_width * size.x as f32);
+                style.height = Val::Px(2. * e.width / tunables.camera_width * size.y as f32);
             } else {
-                style.height = Val::Px(2. * e.height / TARGET_HEIGHT * size.y as f32);
-                style.width = Val::Px(2. * e.height / TARGET_HEIGHT * size.x as f32);
+                style.height = Val::Px(2. * e.height / tunables.camera_height * size.y as f32);
+                style.width = Val::Px(2. * e.height / tunables.camera_height * size.x as f32);
             }
         }
     }
@@ -158,14 +192,30 @@ fn on_resize_system(
 fn update(
     mut blood_e: Query<&mut TextureAtlas, (With<Blood>, Without<Daynight>)>,
     mut daynight_e: Query<(&mut TextureAtlas, &mut UiImage), (Without<Blood>, With<Daynight>)>,
-    time: Res<Time<Virtual>>,
-    player_stats: Query<&Player>
+    mut sun_e: Query<(&mut UiImage, &mut Visibility), (With<SunIndicator>, Without<Daynight>)>,
+    clock: Res<RunClock>,
+    tunables: Res<Tunables>,
+    exposure: Res<SunExposure>,
+    mut heat_e: Query<&mut Text, With<HeatIndicator>>,
+    heat: Res<Heat>,
+    player_health: Query<&Health, With<Player>>
 ){
-    if let Ok(stats) = player_stats.get_single(){
-        blood_e.single_mut().index = ((1. - stats.hp as f32 / stats.max_hp as f32) * 20.).round() as usize % 20;
+    if let Ok(mut text) = heat_e.get_single_mut() {
+        let tier = heat.tier(&tunables);
+        let t = tier as f32 / (HEAT_TIERS.len() - 1) as f32;
+        text.sections[0].value = format!("Heat: {}", HEAT_TIERS[tier]);
+        text.sections[0].style.color = Color::srgb(1., 1. - 0.7 * t, 1. - 0.8 * t);
+    }
+    if let Ok((mut image, mut visibility)) = sun_e.get_single_mut() {
+        let e = exposure.0;
+        *visibility = if e > 0. {Visibility::Inherited} else {Visibility::Hidden};
+        image.color = Color::srgba(1., 1. - 0.5 * e, 1. - 0.7 * e, 0.4 + 0.6 * e);
+    }
+    if let Ok(health) = player_health.get_single(){
+        blood_e.single_mut().index = ((1. - health.current / health.max) * 20.).round() as usize % 20;
     }
     let (mut atlas, mut image) = daynight_e.single_mut();
-    let t = (get_local_time_f(time.elapsed_seconds()) + 0.75) % 1.;
+    let t = (get_local_time_f(clock.elapsed, &tunables) + 0.75) % 1.;
     let d = (t * (29. * 2. - 2.)).ceil() as usize;
     atlas.index = if d < 29 {image.flip_x = false; d} else {image.flip_x = true; (29 * 2) - d - 2};
 }
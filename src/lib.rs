@@ -0,0 +1,105 @@
+pub mod core;
+pub mod player;
+pub mod npc;
+pub mod map;
+pub mod systems;
+pub mod stuff;
+pub mod characters;
+pub mod sounds;
+pub mod menu;
+
+use core::{damage::DamagePlugin, despawn_lifetime::DespawnTimer, functions::despawn_with, rng::reseed_rng, run::{RunPlugin, RunScopedApp}, save::SavePlugin, tunables::TunablesPlugin};
+
+use bevy::prelude::*;
+use characters::plugin::CharacterAnimationPlugin;
+use map::plugin::TileMapPlugin;
+use menu::MenuPlugin;
+use npc::systems::RosesCollected;
+use npc::NPCPlugin;
+use player::{leaderboard::LeaderboardPlugin, PlayerPlugin};
+use sounds::{components::PlaySoundEvent, AudioPlugin};
+use stuff::{simple_anim_update, update_blood_particles, FollowingBloodParticle, FollowingBloodParticlePart};
+use systems::*;
+
+const NUM_ROSES: u32 = 6;
+
+/// Everything that makes up a run, shared by the windowed game and the headless simulation.
+pub struct GamePlugin;
+
+impl Plugin for GamePlugin {
+    fn build(&self, app: &mut App) {
+        app
+        .add_plugins((RunPlugin, TunablesPlugin, DamagePlugin, TileMapPlugin))
+        .init_state::<GameState>()
+        .run_scoped_resource(DayCycle {
+            is_night: true,
+            is_translating: false,
+            darkness: 1.,
+        })
+        .run_scoped_resource(RosesCollected {
+            collected: 0,
+            max: NUM_ROSES,
+        })
+        .run_scoped_resource(RunClock::default())
+        .on_run_restart(reseed_rng)
+        .run_scoped_entities::<FollowingBloodParticle>()
+        .run_scoped_entities::<FollowingBloodParticlePart>()
+        // corpses, souls and other short lived leftovers
+        .run_scoped_entities::<DespawnTimer>()
+        .add_event::<PlaySoundEvent>()
+        .add_plugins((
+            PlayerPlugin,
+            NPCPlugin,
+            CharacterAnimationPlugin,
+        ))
+        .add_systems(Update, (
+            (update_daycycle, update_score).run_if(in_state(GameState::Playing)), 
+            simple_anim_update.run_if(in_state(GameState::Playing)),
+            update_blood_particles.run_if(in_state(GameState::Playing)),
+            toggle_pause,
+        ));
+        for state in GameState::ALL {
+            app.add_systems(OnEnter(state), sync_virtual_time);
+        }
+    }
+}
+
+/// The windowed game, or `--headless [frames]` without a window.
+pub fn run() {
+    if core::functions::has_arg("headless") {
+        let frames = match core::functions::arg_value("headless") {
+            None => core::headless::DEFAULT_FRAMES,
+            Some(v) => match v.parse() {
+                Ok(frames) => frames,
+                Err(_) => {
+                    eprintln!("Invalid frame count {:?} for --headless", v);
+                    std::process::exit(2);
+                }
+            },
+        };
+        core::headless::run_headless(frames);
+        return;
+    }
+    let mut app = App::new();
+    app
+    .add_plugins((
+        core::default::plugin::DefaultPlugin,
+        core::replay::ReplayPlugin,
+        //SwitchableEguiInspectorPlugin,
+        //ScreenDiagnosticsPlugin,
+        GamePlugin,
+        AudioPlugin,
+        MenuPlugin,
+        SavePlugin,
+        LeaderboardPlugin,
+    ))
+    .add_systems(OnEnter(GameState::MainMenu), spawn_starter_screen)
+    .add_systems(OnExit(GameState::MainMenu), despawn_with::<StarterScreen>)
+    .add_systems(Update, (
+        (interact_start_button, interact_continue_button, interact_archetype_button).run_if(not(core::replay::is_replaying)),
+        select_archetype,
+        update_archetype_text,
+        start_game,
+    ).chain().run_if(in_state(GameState::MainMenu)))
+    .run();
+}
@@ -1,21 +1,17 @@
 use std::time::Duration;
 
-use bevy::{math::ivec2, prelude::*, transform::commands, utils::HashSet};
+use bevy::{math::ivec2, prelude::*, utils::HashSet};
 use bevy_ecs_ldtk::prelude::*;
 use bevy_rapier2d::prelude::{ActiveEvents, Collider, RigidBody, Sensor, Velocity};
-use bevy_light_2d::prelude::Light2dPlugin;
-use crate::player::components::Player;
+use crate::{core::run::RunScopedApp, player::components::Player};
 
-use super::tilemap::{self, setup_camera_bounds, update_emitter_tiles, RaycastableTileObsticle, TileObsticle, TransformToGrid};
+use super::tilemap::{self, setup_camera_bounds, update_emitter_tiles, AnimatedTree, RaycastableTileObsticle, Roof, TileObsticle, TransformToGrid};
 
 pub struct TileMapPlugin;
 
 impl Plugin for TileMapPlugin {
     fn build(&self, app: &mut App) {
-        app.add_plugins((
-            LdtkPlugin,
-            Light2dPlugin,
-        ));
+        app.add_plugins(LdtkPlugin);
         app.insert_resource(LevelSelection::Uid(0))
         .insert_resource(LdtkSettings {
             level_spawn_behavior: LevelSpawnBehavior::UseWorldTranslation {
@@ -25,18 +21,27 @@ impl Plugin for TileMapPlugin {
             ..Default::default()
         });
         app.add_event::<RespawnRosesEvent>();
+        app.on_run_restart(restart_collectables);
+        app.run_scoped_resource(RemainingRoses::default());
         app.add_systems(PreStartup, tilemap::pre_setup);
-        app.add_systems(Update, (tilemap::watcher, spawn_collectables, respawn_collectables));
-        app.add_systems(Update, (tilemap::spawn_tile_collision, update_emitter_tiles, setup_camera_bounds, update_unit_grid, tilemap::spawn_tile_tree, tilemap::spawn_raycastable_tile_collision, tilemap::update_animated_trees));
-        app.add_systems(PreUpdate, trespassable_spawn_listener);
+        app.add_systems(Update, (tilemap::watcher, spawn_collectables, respawn_collectables, spawn_coffins));
+        app.add_systems(Update, (tilemap::spawn_tile_collision, setup_camera_bounds, update_unit_grid, tilemap::spawn_raycastable_tile_collision,
+            // all three pull from the cosmetic stream
+            (update_emitter_tiles, tilemap::spawn_tile_tree, tilemap::update_animated_trees).chain()));
+        app.add_systems(PreUpdate, (trespassable_spawn_listener, shade_spawn_listener));
         app.register_ldtk_entity::<HunterSpawnerBundle>("HunterSpawner");
         app.register_ldtk_entity::<CivilianSpawnerBundle>("CivilianSpawner");
         app.register_ldtk_entity::<CollectableRoseBundle>("Rose");
+        app.register_ldtk_entity::<CoffinBundle>("Coffin");
         app.register_ldtk_int_cell_for_layer::<tilemap::RaycastableTileObsticleBundle>("Ground", 1);
         app.register_ldtk_int_cell_for_layer::<tilemap::TiledTreeBundle>("Ground", 3);
         app.register_ldtk_int_cell_for_layer::<tilemap::TileObsticleBundle>("Ground", 4);
         app.register_ldtk_int_cell_for_layer::<tilemap::RaycastableTileObsticleBundle>("Ground", 5);
 
+        app.register_ldtk_int_cell_for_layer::<tilemap::RoofBundle>("Roof", 1);
+        app.register_ldtk_int_cell_for_layer::<tilemap::RoofBundle>("Roof", 2);
+        app.register_ldtk_int_cell_for_layer::<tilemap::RoofBundle>("Roof", 3);
+
         app.register_ldtk_int_cell_for_layer::<tilemap::LightEmitterPYBundle>("Emitters", 1);
         app.register_ldtk_int_cell_for_layer::<tilemap::LightEmitterPYNXBundle>("Emitters", 2);
         app.register_ldtk_int_cell_for_layer::<tilemap::LightEmitterPYPXBundle>("Emitters", 3);
@@ -52,6 +57,7 @@ impl Plugin for TileMapPlugin {
         app.register_ldtk_int_cell_for_layer::<tilemap::LightEmitterAlwaysCampfireBundle>("Emitters", 13);
 
         app.insert_resource(TrespassableCells::default());
+        app.insert_resource(ShadeCells::default());
     }
 }
 
@@ -67,9 +73,23 @@ pub struct CollectableRoseBundle{
     rose: CollectableRoseSpawner
 }
 
+/// Rest spot of the player, a respawn point and a bed to sleep through the day.
+#[derive(Component, Default)]
+pub struct Coffin;
+
+#[derive(Bundle, Default, LdtkEntity)]
+pub struct CoffinBundle{
+    coffin: Coffin
+}
+
 #[derive(Event)]
 pub struct RespawnRosesEvent;
 
+/// `EntityIid`s of the rose spawners that still have their rose, `None` while all do.
+/// Set by a continued run, so spawners loaded after it only bring back the uncollected roses.
+#[derive(Resource, Clone, Default)]
+pub struct RemainingRoses(pub Option<Vec<String>>);
+
 pub fn respawn_collectables(
     asset_server: Res<AssetServer>,
     mut commands: Commands,
@@ -96,12 +116,35 @@ pub fn respawn_collectables(
     }
 }
 
-pub fn spawn_collectables(
-    to_spawn: Query<Entity, Added<CollectableRoseSpawner>>,
+fn restart_collectables(mut event: EventWriter<RespawnRosesEvent>) {
+    event.send(RespawnRosesEvent);
+}
+
+pub fn spawn_coffins(
+    to_spawn: Query<Entity, Added<Coffin>>,
     asset_server: Res<AssetServer>,
     mut commands: Commands,
 ){
     for e in to_spawn.iter(){
+        commands.entity(e).with_children(|commands|{commands.spawn((
+            SpriteBundle{
+                texture: asset_server.load("map/coffin.png"),
+                transform: Transform::from_xyz(0., 0., 1.),
+                ..default()
+            },
+            Name::new("Coffin"),
+        ));});
+    }
+}
+
+pub fn spawn_collectables(
+    to_spawn: Query<(Entity, &EntityIid), Added<CollectableRoseSpawner>>,
+    remaining: Res<RemainingRoses>,
+    asset_server: Res<AssetServer>,
+    mut commands: Commands,
+){
+    for (e, iid) in to_spawn.iter(){
+        if remaining.0.as_ref().is_some_and(|r| !r.iter().any(|r| r == iid.as_str())) {continue}
         commands.entity(e).with_children(|commands|{commands.spawn((
             SpriteBundle{
                 texture: asset_server.load("map/rose.png"),
@@ -133,17 +176,22 @@ pub struct TrespassableCellBundle{
 #[derive(Clone, Eq, PartialEq, Debug, Default, Component)]
 pub struct HunterSpawner {
     pub timer: Timer,
+    /// Lowest heat tier the spawner works at.
+    pub heat_tier: usize,
 }
 
 #[derive(Clone, Debug, Bundle, LdtkEntity)]
 pub struct HunterSpawnerBundle {
     spanwer: HunterSpawner,
+    #[grid_coords]
+    grid_coords: GridCoords,
 }
 
 impl Default for HunterSpawnerBundle {
     fn default() -> Self {
         Self {
-            spanwer: HunterSpawner { timer: Timer::new(Duration::from_secs_f32(0.5), TimerMode::Repeating) }
+            spanwer: HunterSpawner { timer: Timer::new(Duration::from_secs_f32(0.5), TimerMode::Repeating), heat_tier: 0 },
+            grid_coords: GridCoords::default(),
         }
     }
 }
@@ -184,6 +232,56 @@ impl TrespassableCells {
 
 
 
+/// Grid cells (as in [`TransformToGrid`]) out of the sun: under roofs and trees,
+/// and the row south of buildings, where their shadow falls.
+#[derive(Resource, Default)]
+pub struct ShadeCells{
+    pub cells: HashSet<IVec2>,
+    pub ready: bool
+}
+
+impl ShadeCells {
+    pub fn is_shaded(&self, pos: &IVec2) -> bool{
+        self.cells.contains(pos)
+    }
+}
+
+/// Waits for the level, roofs spawn along with the rest of it.
+/// Starts over whenever the level is spawned again, e.g. after editing it in LDtk.
+fn shade_spawn_listener(
+    mut level_events: EventReader<LevelEvent>,
+    roofs: Query<&GridCoords, With<Roof>>,
+    trees: Query<&GridCoords, With<AnimatedTree>>,
+    buildings: Query<&GridCoords, (With<TileObsticle>, Without<AnimatedTree>)>,
+    mut shade: ResMut<ShadeCells>,
+    transfromer: Res<TransformToGrid>,
+){
+    if level_events.read().any(|e| matches!(e, LevelEvent::Spawned(_))) {
+        *shade = ShadeCells::default();
+    }
+    if shade.ready || roofs.is_empty() || !transfromer.ready {return}
+    let to_grid = |coords: &GridCoords| ivec2(coords.x, transfromer.grid_size.y - coords.y - 1);
+    for coords in roofs.iter(){
+        shade.cells.insert(to_grid(coords));
+    }
+    // the canopy is wider than the trunk
+    for coords in trees.iter(){
+        let pos = to_grid(coords);
+        for x in -1..=1 {
+            for y in -1..=1 {
+                shade.cells.insert(pos + ivec2(x, y));
+            }
+        }
+    }
+    for coords in buildings.iter(){
+        let pos = to_grid(coords);
+        shade.cells.insert(pos);
+        shade.cells.insert(pos + ivec2(0, 1));
+    }
+    info!("Shade cells inited!");
+    shade.ready = true;
+}
+
 fn update_unit_grid(
     mut trespassable: ResMut<TrespassableCells>,
     transfromer: Res<TransformToGrid>,
@@ -2,11 +2,10 @@ use bevy::{math::{ivec2, uvec2, vec2, vec3}, prelude::*, utils::{HashMap, HashSe
 use bevy_ecs_ldtk::prelude::*;
 use bevy_light_2d::light::PointLight2d;
 use bevy_rapier2d::{dynamics::RigidBody, geometry::{Collider, Friction}, prelude::{CollisionGroups, Group}};
-use noise::{core::perlin, NoiseFn, Perlin};
+use noise::{NoiseFn, Perlin};
 use rand::Rng;
-use bevy_easings::*;
 
-use crate::{core::{camera::plugin::{CameraController, MainCamera}, functions::TextureAtlasLayoutHandles, post_processing::PostProcessUniform}, player::{components::Player, systems::{RAYCASTABLE_STRUCT_CG, STRUCTURES_CG}}, stuff::fire_bundle, DayCycle};
+use crate::{core::{camera::plugin::CameraController, functions::TextureAtlasLayoutHandles, input::{Action, ActionState}, post_processing::PostProcessUniform, rng::GameRng, settings::Settings}, player::{components::Player, systems::{RAYCASTABLE_STRUCT_CG, STRUCTURES_CG}}, stuff::fire_bundle, DayCycle};
 
 #[derive(Component)]
 pub struct Structure;
@@ -39,7 +38,7 @@ pub fn watcher (
     levels: Query<(&LevelIid, &GlobalTransform)>,
 ){
     if res.ready {return;}
-    for (level_iid, level_transform) in levels.iter() {
+    if let Some((level_iid, level_transform)) = levels.iter().next() {
         let ldtk_project = ldtk_project_assets
             .get(ldtk_projects.single())
             .expect("ldtk project should be loaded before player is spawned");
@@ -53,7 +52,6 @@ pub fn watcher (
             ready: true,
             grid_size: ivec2(level.px_wid / 16, level.px_hei / 16)
         });
-        return;
     }
 }
 
@@ -95,6 +93,15 @@ pub struct RaycastableTileObsticleBundle {
     obsticle: RaycastableTileObsticle,
 }
 
+/// A cell of the `Roof` layer, the player is in the shade under it.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Default, Component)]
+pub struct Roof;
+
+#[derive(Clone, Debug, Default, Bundle, LdtkIntCell)]
+pub struct RoofBundle {
+    roof: Roof,
+}
+
 #[derive(Clone, Debug, Default, Bundle, LdtkIntCell)]
 pub struct TiledTreeBundle {
     obsticle: TileObsticle,
@@ -168,7 +175,8 @@ pub fn update_emitter_tiles(
     post_process: Query<&PostProcessUniform>,
     daycycle: Res<DayCycle>,
     asset_server: Res<AssetServer>,
-    time: Res<Time<Virtual>>
+    time: Res<Time<Virtual>>,
+    mut rng: ResMut<GameRng>,
 ){
     let max_intensity = 0.8;
     let default_radius = 150.;
@@ -179,7 +187,7 @@ pub fn update_emitter_tiles(
         c.spawn((
             bevy_light_2d::light::PointLight2dBundle{
                 point_light: PointLight2d{
-                    color: color,
+                    color,
                     intensity: max_intensity,
                     radius: default_radius,
                     falloff: default_falloff,
@@ -221,7 +229,7 @@ pub fn update_emitter_tiles(
                 },
                 LightEmitter,
             )).insert(
-                fire_bundle(&asset_server, &mut handles, rand::thread_rng().gen_range(0..19))
+                fire_bundle(&asset_server, &mut handles, rng.cosmetic().gen_range(0..19))
             ).insert(Transform::from_translation(vec3(0., 10., 1.)));
         });
         commands.entity(e).insert(Transform::from_xyz(0., 0., 0.));
@@ -248,7 +256,7 @@ pub fn update_emitter_tiles(
                 },
                 LightEmitter,
             )).insert(
-                fire_bundle(&asset_server, &mut handles, rand::thread_rng().gen_range(0..19))
+                fire_bundle(&asset_server, &mut handles, rng.cosmetic().gen_range(0..19))
             ).insert(Transform::from_translation(vec3(0., 10., 1.)));
         });
         commands.entity(e).insert(Transform::from_xyz(0., 0., 0.));
@@ -274,18 +282,19 @@ pub fn update_emitter_tiles(
                 },
                 LightEmitter,
             )).insert(
-                fire_bundle(&asset_server, &mut handles, rand::thread_rng().gen_range(0..19))
+                fire_bundle(&asset_server, &mut handles, rng.cosmetic().gen_range(0..19))
             ).insert(Transform::from_translation(vec3(0., 12., -9.)));
         });
     }
 
-    let p = post_process.single();
-    for mut l in emitters.iter_mut(){
+    if let Ok(p) = post_process.get_single() {
+        for mut l in emitters.iter_mut(){
 
-        l.intensity = p.daytime.powi(2) * max_intensity;
-        
-        if (time.elapsed_seconds() * 16.).round() as usize % 2 == 0 {
-            l.radius = default_radius + rand::thread_rng().gen_range(0..100) as f32 * 0.01 * 0.4 * default_radius;
+            l.intensity = p.daytime.powi(2) * max_intensity;
+            
+            if ((time.elapsed_seconds() * 16.).round() as usize).is_multiple_of(2) {
+                l.radius = default_radius + rng.cosmetic().gen_range(0..100) as f32 * 0.01 * 0.4 * default_radius;
+            }
         }
     }
     if daycycle.is_night || daycycle.is_translating{
@@ -300,22 +309,25 @@ pub fn update_animated_trees(
     mut tree_q: Query<(&GlobalTransform, &mut Transform, &AnimatedTreePart, &mut Sprite), Without<Player>>,
     time: Res<Time<Virtual>>,
     mut perlin: Local<Option<Perlin>>,
-    mut is_static: Local<bool>,
-    keyboard: Res<ButtonInput<KeyCode>>,
-    player: Query<&Transform, (With<Player>, Without<AnimatedTree>)>
+    mut settings: ResMut<Settings>,
+    actions: Res<ActionState>,
+    player: Query<&Transform, (With<Player>, Without<AnimatedTree>)>,
+    mut rng: ResMut<GameRng>,
 ){
-    if keyboard.just_pressed(KeyCode::F4){
-        *is_static = !*is_static;
-        if *is_static {
+    if actions.just_pressed(Action::ToggleStaticTrees){
+        settings.static_trees = !settings.static_trees;
+    }
+    if settings.static_trees {
+        if settings.is_changed() {
             tree_q.par_iter_mut().for_each(|(_, mut transform, tree, _)|{
                 if tree.0 == 0 {return;}
                 transform.rotation = Quat::from_rotation_z(0.);
             });
         }
+        return;
     }
-    if *is_static {return;}
 
-    if perlin.is_none(){*perlin = Some(Perlin::new(rand::thread_rng().gen::<u32>()))}
+    if perlin.is_none(){*perlin = Some(Perlin::new(rng.cosmetic().gen::<u32>()))}
     
     let pos = player.get_single();
     let pos = if let Ok(pos) = pos {pos.translation.xy()} else {Vec2::ZERO};
@@ -345,10 +357,11 @@ pub fn spawn_tile_tree(
     mut commands: Commands,
     tree_q: Query<Entity, Added<AnimatedTree>>,
     asset_server: Res<AssetServer>,
-    mut layout_handles: ResMut<TextureAtlasLayoutHandles>
+    mut layout_handles: ResMut<TextureAtlasLayoutHandles>,
+    mut rng: ResMut<GameRng>,
 ) {
     if !tree_q.is_empty() {
-        let mut r = rand::thread_rng();
+        let r = rng.cosmetic();
         for new_tree in tree_q.iter(){
             commands.entity(new_tree).with_children(|cmd|{
                 cmd.spawn((
@@ -422,7 +435,6 @@ pub fn spawn_tile_tree(
 }
 
 pub fn setup_camera_bounds(
-    mut commands: Commands,
     mut cameras_q: Query<&mut CameraController>,
     level_query: Query<(&Transform, &LevelIid)>,
     ldtk_projects: Query<&Handle<LdtkProject>>,
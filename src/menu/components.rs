@@ -0,0 +1,25 @@
+use bevy::prelude::*;
+
+use crate::player::components::MenuChoice;
+
+#[derive(Component)]
+pub struct PauseScreen;
+
+#[derive(Component)]
+pub struct SettingsScreen;
+
+#[derive(Component)]
+pub struct SummaryScreen;
+
+/// Entry of a keyboard navigable menu, `index` is its place from the top.
+#[derive(Component, Clone, Copy)]
+pub struct MenuButton {
+    pub choice: MenuChoice,
+    pub index: usize,
+}
+
+/// Entry the keyboard or gamepad (or the last hovered button) points at.
+#[derive(Resource, Default)]
+pub struct MenuFocus {
+    pub index: usize,
+}
@@ -0,0 +1,39 @@
+pub mod systems;
+pub mod components;
+
+use bevy::prelude::*;
+
+use components::*;
+use systems::*;
+
+use crate::{core::{functions::despawn_with, replay::is_replaying}, systems::GameState};
+
+pub struct MenuPlugin;
+
+impl Plugin for MenuPlugin {
+    fn build(&self, app: &mut App) {
+        let in_menu = in_state(GameState::Paused)
+            .or_else(in_state(GameState::Settings))
+            .or_else(in_state(GameState::Summary));
+        app
+        .init_resource::<MenuFocus>()
+        .add_systems(OnEnter(GameState::Paused), spawn_pause_screen)
+        .add_systems(OnExit(GameState::Paused), despawn_with::<PauseScreen>)
+        .add_systems(OnEnter(GameState::Settings), spawn_settings_screen)
+        .add_systems(OnExit(GameState::Settings), despawn_with::<SettingsScreen>)
+        .add_systems(OnEnter(GameState::Summary), spawn_summary_screen)
+        .add_systems(OnExit(GameState::Summary), despawn_with::<SummaryScreen>)
+        .add_systems(Update, (
+            interact_menu_button.run_if(not(is_replaying)),
+            navigate_menu,
+            confirm_menu.run_if(not(is_replaying)),
+            highlight_menu,
+            (
+                apply_pause_choice.run_if(in_state(GameState::Paused)),
+                apply_summary_choice.run_if(in_state(GameState::Summary)),
+                (apply_settings_choice, update_setting_labels).chain().run_if(in_state(GameState::Settings)),
+            ),
+        ).chain().run_if(in_menu))
+        ;
+    }
+}
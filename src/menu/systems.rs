@@ -0,0 +1,309 @@
+use bevy::prelude::*;
+
+use crate::{core::{input::Rebinding, run::RestartRun, save::SaveRun, settings::{SettingEntry, Settings}}, npc::systems::RosesCollected, player::{components::{MenuChoice, PlayerInput}, stats::RunStats, upgrades::{TakenUpgrades, UpgradeRegistry}}, sounds::components::PlaySoundEvent, systems::GameState};
+
+use super::components::*;
+
+const TEXT_COLOR: Color = Color::srgb(169. / 255., 96. / 255., 45. / 255.);
+
+fn spawn_menu_screen(
+    commands: &mut Commands,
+    asset_server: &Res<AssetServer>,
+    focus: &mut ResMut<MenuFocus>,
+    title: &str,
+    entries: &[(&str, MenuChoice)],
+    screen: impl Bundle,
+) -> Entity {
+    focus.index = 0;
+    let font = asset_server.load("fonts/Monocraft.ttf");
+    commands.spawn((
+        ImageBundle {
+            style: Style {
+                align_self: AlignSelf::Center,
+                justify_self: JustifySelf::Center,
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                flex_direction: FlexDirection::Column,
+                width: Val::Px(500.),
+                height: Val::Px(800.),
+                ..default()
+            },
+            image: UiImage::from(asset_server.load("scroll.png")),
+            ..default()
+        },
+        Name::new(title.to_string()),
+        screen,
+    )).with_children(|parent| {
+        parent.spawn(TextBundle {
+            style: Style {
+                margin: UiRect::bottom(Val::Px(30.)),
+                ..default()
+            },
+            text: Text {
+                sections: vec![TextSection::new(title, TextStyle { font: font.clone(), font_size: 32., color: TEXT_COLOR })],
+                ..default()
+            },
+            ..default()
+        });
+        for (index, (text, choice)) in entries.iter().enumerate() {
+            spawn_menu_button(parent, asset_server, font.clone(), text, *choice, index);
+        }
+    }).id()
+}
+
+fn spawn_menu_button(
+    parent: &mut ChildBuilder,
+    asset_server: &Res<AssetServer>,
+    font: Handle<Font>,
+    text: &str,
+    choice: MenuChoice,
+    index: usize,
+) {
+    parent.spawn((ButtonBundle {
+        style: Style {
+            width: Val::Px(150.),
+            height: Val::Px(30.),
+            justify_items: JustifyItems::Center,
+            justify_content: JustifyContent::Center,
+            margin: UiRect::bottom(Val::Px(10.0)),
+            ..default()
+        },
+        image: UiImage::from(asset_server.load("button.png")),
+        ..default()
+    },
+    MenuButton { choice, index },
+    )).with_children(|parent| {
+        parent.spawn(TextBundle {
+            style: Style {
+                align_self: AlignSelf::Center,
+                justify_self: JustifySelf::Center,
+                ..default()
+            },
+            text: Text {
+                sections: vec![TextSection::new(text, TextStyle { font, font_size: 16., color: TEXT_COLOR })],
+                ..default()
+            },
+            ..default()
+        });
+    });
+}
+
+pub fn spawn_pause_screen(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut focus: ResMut<MenuFocus>,
+) {
+    spawn_menu_screen(&mut commands, &asset_server, &mut focus, "Paused", &[
+        ("Resume", MenuChoice::Resume),
+        ("Restart Run", MenuChoice::Restart),
+        ("Settings", MenuChoice::Settings),
+        ("Save & Quit", MenuChoice::Quit),
+    ], PauseScreen);
+}
+
+/// The stats sit between the title and the buttons.
+pub fn spawn_summary_screen(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut focus: ResMut<MenuFocus>,
+    stats: Res<RunStats>,
+    roses: Res<RosesCollected>,
+    registry: Res<UpgradeRegistry>,
+    taken: Res<TakenUpgrades>,
+) {
+    let screen = spawn_menu_screen(&mut commands, &asset_server, &mut focus, "Summary", &[
+        ("Restart", MenuChoice::Restart),
+        ("Menu", MenuChoice::MainMenu),
+    ], SummaryScreen);
+    let mut lines = stats.lines();
+    lines.push(format!("Roses: {} / {}", roses.collected, roses.max));
+    let upgrades = taken.summary(&registry);
+    if !upgrades.is_empty() {
+        lines.push("Upgrades:".to_string());
+        lines.extend(upgrades.iter().map(|u| format!("  {}", u)));
+    }
+    let font = asset_server.load("fonts/Monocraft.ttf");
+    let text = commands.spawn(TextBundle {
+        style: Style {
+            margin: UiRect::bottom(Val::Px(20.)),
+            ..default()
+        },
+        text: Text {
+            sections: vec![TextSection::new(lines.join("\n"), TextStyle { font, font_size: 14., color: TEXT_COLOR })],
+            ..default()
+        },
+        ..default()
+    }).id();
+    commands.entity(screen).insert_children(1, &[text]);
+}
+
+pub fn spawn_settings_screen(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut focus: ResMut<MenuFocus>,
+    settings: Res<Settings>,
+) {
+    let labels: Vec<(String, MenuChoice)> = SettingEntry::all().into_iter()
+        .map(|entry| (settings.label(entry), MenuChoice::Setting(entry)))
+        .chain([("Back".to_string(), MenuChoice::Back)])
+        .collect();
+    let entries: Vec<(&str, MenuChoice)> = labels.iter().map(|(label, choice)| (label.as_str(), *choice)).collect();
+    spawn_menu_screen(&mut commands, &asset_server, &mut focus, "Settings", &entries, SettingsScreen);
+}
+
+pub fn interact_menu_button(
+    button_q: Query<(&Interaction, &MenuButton), Changed<Interaction>>,
+    mut focus: ResMut<MenuFocus>,
+    mut input: ResMut<PlayerInput>,
+    mut play_sound: EventWriter<PlaySoundEvent>,
+) {
+    for (interaction, button) in button_q.iter() {
+        match *interaction {
+            Interaction::Pressed => {
+                input.menu = Some(button.choice);
+            }
+            Interaction::Hovered => {
+                if focus.index != button.index {
+                    focus.index = button.index;
+                    play_sound.send(PlaySoundEvent::Select);
+                }
+            }
+            Interaction::None => {}
+        }
+    }
+}
+
+pub fn navigate_menu(
+    buttons: Query<&MenuButton>,
+    mut focus: ResMut<MenuFocus>,
+    input: Res<PlayerInput>,
+    mut play_sound: EventWriter<PlaySoundEvent>,
+) {
+    let count = buttons.iter().len() as i32;
+    if count == 0 || input.menu_nav == 0 {return}
+    focus.index = (focus.index as i32 + input.menu_nav).rem_euclid(count) as usize;
+    play_sound.send(PlaySoundEvent::Select);
+}
+
+pub fn confirm_menu(
+    buttons: Query<&MenuButton>,
+    focus: Res<MenuFocus>,
+    mut input: ResMut<PlayerInput>,
+) {
+    if !input.confirm {return}
+    if let Some(button) = buttons.iter().find(|b| b.index == focus.index) {
+        input.menu = Some(button.choice);
+    }
+}
+
+pub fn highlight_menu(
+    mut buttons: Query<(&MenuButton, &mut UiImage)>,
+    added: Query<(), Added<MenuButton>>,
+    focus: Res<MenuFocus>,
+    asset_server: Res<AssetServer>,
+) {
+    if !focus.is_changed() && added.is_empty() {return}
+    for (button, mut image) in buttons.iter_mut() {
+        let texture = if button.index == focus.index {"select_button.png"} else {"button.png"};
+        *image = UiImage::from(asset_server.load(texture));
+    }
+}
+
+pub fn apply_pause_choice(
+    input: Res<PlayerInput>,
+    mut next_state: ResMut<NextState<GameState>>,
+    mut restart: EventWriter<RestartRun>,
+    mut save: EventWriter<SaveRun>,
+    mut exit: EventWriter<AppExit>,
+    mut play_sound: EventWriter<PlaySoundEvent>,
+) {
+    let Some(choice) = input.menu else {return};
+    match choice {
+        MenuChoice::Resume => next_state.set(GameState::Playing),
+        MenuChoice::Restart => {
+            restart.send(RestartRun);
+            next_state.set(GameState::Playing);
+        },
+        MenuChoice::Settings => next_state.set(GameState::Settings),
+        MenuChoice::Quit => {
+            save.send(SaveRun);
+            exit.send(AppExit::Success);
+        },
+        _ => return,
+    }
+    play_sound.send(PlaySoundEvent::Selected);
+}
+
+/// Both start a fresh run, the menu one waits on the start screen.
+pub fn apply_summary_choice(
+    input: Res<PlayerInput>,
+    mut next_state: ResMut<NextState<GameState>>,
+    mut restart: EventWriter<RestartRun>,
+    mut play_sound: EventWriter<PlaySoundEvent>,
+) {
+    match input.menu {
+        Some(MenuChoice::Restart) => next_state.set(GameState::Playing),
+        Some(MenuChoice::MainMenu) => next_state.set(GameState::MainMenu),
+        _ => return,
+    }
+    restart.send(RestartRun);
+    play_sound.send(PlaySoundEvent::Selected);
+}
+
+/// Clicking or confirming a setting steps it forward, left/right steps the focused one.
+/// Bindings instead wait for the next pressed button.
+pub fn apply_settings_choice(
+    input: Res<PlayerInput>,
+    buttons: Query<&MenuButton>,
+    focus: Res<MenuFocus>,
+    mut settings: ResMut<Settings>,
+    mut rebinding: ResMut<Rebinding>,
+    mut next_state: ResMut<NextState<GameState>>,
+    mut play_sound: EventWriter<PlaySoundEvent>,
+) {
+    if rebinding.0.is_some() {return}
+    match input.menu {
+        Some(MenuChoice::Back) => {
+            next_state.set(GameState::Paused);
+            play_sound.send(PlaySoundEvent::Selected);
+            return;
+        },
+        Some(MenuChoice::Setting(SettingEntry::Bind(action))) => {
+            rebinding.0 = Some(action);
+            play_sound.send(PlaySoundEvent::Selected);
+            return;
+        },
+        Some(MenuChoice::Setting(entry)) => {
+            settings.adjust(entry, 1);
+            play_sound.send(PlaySoundEvent::Selected);
+            return;
+        },
+        _ => {}
+    }
+    if input.menu_adjust == 0 {return}
+    let focused = buttons.iter().find(|b| b.index == focus.index).map(|b| b.choice);
+    let Some(MenuChoice::Setting(entry)) = focused else {return};
+    settings.adjust(entry, input.menu_adjust);
+    play_sound.send(PlaySoundEvent::Select);
+}
+
+pub fn update_setting_labels(
+    settings: Res<Settings>,
+    rebinding: Res<Rebinding>,
+    buttons: Query<(&MenuButton, &Children)>,
+    mut texts: Query<&mut Text>,
+) {
+    if !settings.is_changed() && !rebinding.is_changed() {return}
+    for (button, children) in buttons.iter() {
+        let MenuChoice::Setting(entry) = button.choice else {continue};
+        let label = match entry {
+            SettingEntry::Bind(action) if rebinding.0 == Some(action) => format!("{}: press a key", action.name()),
+            _ => settings.label(entry),
+        };
+        for child in children.iter() {
+            if let Ok(mut text) = texts.get_mut(*child) {
+                text.sections[0].value = label.clone();
+            }
+        }
+    }
+}
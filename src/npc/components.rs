@@ -1,4 +1,5 @@
-use bevy::{prelude::*, time::Stopwatch};
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
 
 #[derive(Component)]
 pub struct Civilian;
@@ -24,12 +25,12 @@ pub struct NpcVelAccum {
     pub v: Vec2,
 }
 
-#[derive(Component)]
+#[derive(Component, Clone, Debug, Serialize, Deserialize)]
 pub struct NpcPath {
     pub path: Option<Vec<IVec2>>,
 }
 
-#[derive(Component, Clone, Copy, PartialEq, Debug)]
+#[derive(Component, Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
 pub enum NpcState {
     Attack,
     Escape,
@@ -49,6 +50,25 @@ pub struct PlayerLastPos {
     pub pos: IVec2,
 }
 
+/// Paces a hunter's calls to its squad while it sees the player.
+#[derive(Component)]
+pub struct ShoutTimer {
+    pub timer: Timer,
+}
+
+impl ShoutTimer {
+    /// Starts finished, the first sighting is called out right away.
+    pub fn new(secs: f32) -> Self {
+        let mut timer = Timer::from_seconds(secs, TimerMode::Once);
+        timer.tick(timer.duration());
+        ShoutTimer {timer}
+    }
+}
+
+/// On a civilian the player is feeding on, the ai leaves it alone until it is let go.
+#[derive(Component)]
+pub struct Held;
+
 #[derive(Component)]
 pub struct AttackTimer {
     pub timer: Timer,
@@ -0,0 +1,110 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    core::{damage::{DamageTaken, Health}, tunables::Tunables},
+    player::{components::{KillNpc, Player}, upgrades::TakenUpgrades},
+    systems::RunClock,
+};
+
+/// Paces the hunters over the run: every night spends a bigger hunter budget and the
+/// hunters get faster, throw harder and more often, and see further. The player's level
+/// pushes the same way.
+/// On top of that a beating eases off for a while, a kill streak pushes back.
+#[derive(Resource, Clone, Debug, Serialize, Deserialize)]
+pub struct Director {
+    /// The current night, or the last one by day, counted from 1.
+    pub night: u32,
+    /// Hunters the spawners may still bring in until the next night.
+    pub budget: u32,
+    /// From the night and the level, 0 on the first night up to `difficulty_cap`.
+    pub difficulty: f32,
+    /// Damage taken lately, fades out over `damage_memory`.
+    pub recent_damage: f32,
+    pub kill_streak: u32,
+    /// Seconds left to extend the kill streak.
+    streak_timer: f32,
+    /// Multiplies the difficulty and the spawn chance, below 1 after a beating, above during a streak.
+    pub pace: f32,
+}
+
+impl Default for Director {
+    fn default() -> Self {
+        Director {night: 0, budget: 0, difficulty: 0., recent_damage: 0., kill_streak: 0, streak_timer: 0., pace: 1.}
+    }
+}
+
+impl Director {
+    fn scale(&self, per_difficulty: f32) -> f32 {
+        1. + self.difficulty * self.pace * per_difficulty
+    }
+
+    /// Multiplies the hunters' max speed and acceleration.
+    pub fn hunter_speed(&self, tunables: &Tunables) -> f32 {
+        self.scale(tunables.hunter_speed_scale)
+    }
+
+    pub fn proj_v(&self, tunables: &Tunables) -> f32 {
+        tunables.proj_v * self.scale(tunables.proj_v_scale)
+    }
+
+    /// Multiplies how fast the hunters' throw timers run.
+    pub fn throw_rate(&self, tunables: &Tunables) -> f32 {
+        self.scale(tunables.throw_rate_scale)
+    }
+
+    /// Multiplies the hunters' sight range.
+    pub fn sight(&self, tunables: &Tunables) -> f32 {
+        self.scale(tunables.sight_scale)
+    }
+
+    pub fn spawn_chance(&self, tunables: &Tunables) -> f64 {
+        (tunables.spawn_chance * self.pace as f64).clamp(0., 1.)
+    }
+}
+
+/// The night is read off the run clock, so a continued run picks up where it was.
+pub fn update_director(
+    mut director: ResMut<Director>,
+    mut taken: EventReader<DamageTaken>,
+    mut kill_npc: EventReader<KillNpc>,
+    player: Query<(Entity, &Health), With<Player>>,
+    upgrades: Res<TakenUpgrades>,
+    clock: Res<RunClock>,
+    tunables: Res<Tunables>,
+    time: Res<Time>,
+) {
+    let night = (clock.elapsed / tunables.cycle_duration()) as u32 + 1;
+    if night != director.night {
+        director.night = night;
+        director.budget = tunables.hunter_budget + tunables.hunter_budget_per_night * (night - 1);
+    }
+    let level = upgrades.taken.len() as f32;
+    director.difficulty = ((night - 1) as f32 * tunables.difficulty_per_night + level * tunables.difficulty_per_level)
+        .min(tunables.difficulty_cap);
+
+    let dt = time.delta_seconds();
+    // drained even without a player, so stale events don't pile onto the next one
+    let hits: Vec<(Entity, f32)> = taken.read().map(|hit| (hit.target, hit.amount)).collect();
+    let kills = kill_npc.read().count() as u32;
+    let Ok((entity, health)) = player.get_single() else {return};
+    director.recent_damage *= (-dt / tunables.damage_memory).exp();
+    director.recent_damage += hits.iter()
+        .filter(|(target, _)| *target == entity)
+        .map(|(_, amount)| amount)
+        .sum::<f32>();
+
+    if kills > 0 {
+        director.kill_streak += kills;
+        director.streak_timer = tunables.streak_window;
+    }
+    director.streak_timer -= dt;
+    if director.streak_timer <= 0. {
+        director.kill_streak = 0;
+    }
+
+    // half the difficulty off at most, and half on
+    let relief = (director.recent_damage / (health.max * tunables.relief_damage)).min(1.) * 0.5;
+    let tension = (director.kill_streak as f32 * tunables.streak_tension).min(0.5);
+    director.pace = 1. + tension - relief;
+}
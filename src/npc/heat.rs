@@ -0,0 +1,125 @@
+use bevy::prelude::*;
+use bevy_ecs_ldtk::GridCoords;
+use bevy_rapier2d::prelude::RapierContext;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    core::{functions::TextureAtlasLayoutHandles, rng::GameRng, tunables::Tunables},
+    map::plugin::HunterSpawner,
+    player::components::{KillNpc, Player},
+    stuff::{spawn_warn_particle, Corpse},
+    systems::DayCycle,
+};
+
+use super::{components::{Civilian, Hunter, NpcState}, systems::raycast};
+
+/// Names of the heat tiers, from none to the whole village out hunting.
+pub const HEAT_TIERS: [&str; 4] = ["Calm", "Wary", "Alarmed", "Hunted"];
+
+/// How much the village knows about the vampire. Witnessed kills and found corpses raise it,
+/// it cools off by day. Higher tiers open more hunter spawners, send patrols out by day
+/// and have night hunters come in already chasing.
+#[derive(Resource, Default, Clone, Debug, Serialize, Deserialize)]
+pub struct Heat {
+    pub value: f32,
+}
+
+impl Heat {
+    pub fn tier(&self, tunables: &Tunables) -> usize {
+        ((self.value / tunables.heat_per_tier) as usize).min(HEAT_TIERS.len() - 1)
+    }
+
+    /// Capped one tier's worth over the last threshold, so a cool day always brings it down.
+    pub fn raise(&mut self, amount: f32, tunables: &Tunables) {
+        self.value = (self.value + amount).min(tunables.heat_per_tier * HEAT_TIERS.len() as f32);
+    }
+}
+
+/// On an npc whose death was seen, its body raises no more heat when it is found.
+#[derive(Component)]
+pub struct Witnessed;
+
+/// Nothing but the player in the way, npcs see over each other.
+fn sees(from: Vec2, to: Vec2, player: Entity, rapier_context: &Res<RapierContext>) -> bool {
+    let direction = to - from;
+    let length = direction.length();
+    if length < f32::EPSILON {return true}
+    match raycast(from, direction / length, length, rapier_context) {
+        Some(entity) => entity == player,
+        None => true,
+    }
+}
+
+/// A kill seen by any living npc in sight range raises the heat once, finding the body adds nothing.
+pub fn witness_kills(
+    mut commands: Commands,
+    mut kill_npc: EventReader<KillNpc>,
+    npcs: Query<(&Transform, &NpcState, Has<Hunter>), Or<(With<Civilian>, With<Hunter>)>>,
+    player: Query<Entity, With<Player>>,
+    rapier_context: Res<RapierContext>,
+    mut heat: ResMut<Heat>,
+    mut layout_handles: ResMut<TextureAtlasLayoutHandles>,
+    asset_server: Res<AssetServer>,
+    mut rng: ResMut<GameRng>,
+    tunables: Res<Tunables>,
+) {
+    let Ok(player) = player.get_single() else {return};
+    for kill in kill_npc.read() {
+        let witness = npcs.iter()
+            .filter(|(_, state, _)| **state != NpcState::Dead)
+            .map(|(t, _, is_hunter)| (t.translation.xy(), if is_hunter {tunables.spot_dist} else {tunables.spot_dist_civ}))
+            .find(|(pos, sight)| pos.distance(kill.pos) < *sight && sees(*pos, kill.pos, player, &rapier_context));
+        let Some((witness_pos, _)) = witness else {continue};
+        spawn_warn_particle(&mut commands, &mut layout_handles, &asset_server, &mut rng, witness_pos.extend(0.));
+        heat.raise(tunables.heat_witnessed_kill, &tunables);
+        if let Some(mut npc) = commands.get_entity(kill.npc) {
+            npc.insert(Witnessed);
+        }
+    }
+}
+
+/// A corpse raises the heat once, the first time a living npc in sight range sees it.
+pub fn find_corpses(
+    mut commands: Commands,
+    corpses: Query<(Entity, &GlobalTransform), With<Corpse>>,
+    npcs: Query<(&Transform, &NpcState), Or<(With<Civilian>, With<Hunter>)>>,
+    player: Query<Entity, With<Player>>,
+    rapier_context: Res<RapierContext>,
+    mut heat: ResMut<Heat>,
+    mut layout_handles: ResMut<TextureAtlasLayoutHandles>,
+    asset_server: Res<AssetServer>,
+    mut rng: ResMut<GameRng>,
+    tunables: Res<Tunables>,
+) {
+    let Ok(player) = player.get_single() else {return};
+    for (corpse, corpse_transform) in corpses.iter() {
+        let corpse_pos = corpse_transform.translation().xy();
+        let finder = npcs.iter()
+            .filter(|(_, state)| **state != NpcState::Dead)
+            .map(|(t, _)| t.translation.xy())
+            .find(|pos| pos.distance(corpse_pos) < tunables.spot_dist_civ && sees(*pos, corpse_pos, player, &rapier_context));
+        let Some(finder_pos) = finder else {continue};
+        spawn_warn_particle(&mut commands, &mut layout_handles, &asset_server, &mut rng, finder_pos.extend(0.));
+        heat.raise(tunables.heat_corpse, &tunables);
+        commands.entity(corpse).remove::<Corpse>();
+    }
+}
+
+pub fn cool_heat(
+    mut heat: ResMut<Heat>,
+    day_cycle: Res<DayCycle>,
+    tunables: Res<Tunables>,
+    time: Res<Time>,
+) {
+    if day_cycle.is_night {return}
+    heat.value = (heat.value - tunables.heat_decay * time.delta_seconds()).max(0.);
+}
+
+/// Half of the hunter spawners always work, the rest open up one tier at a time.
+/// Spread by grid position, so the same spawners open on every run.
+pub fn assign_spawner_tiers(mut spawners: Query<(&mut HunterSpawner, &GridCoords), Added<HunterSpawner>>) {
+    for (mut spawner, coords) in spawners.iter_mut() {
+        let slot = (coords.x * 7 + coords.y * 13).rem_euclid(HEAT_TIERS.len() as i32 + 2) as usize;
+        spawner.heat_tier = slot.saturating_sub(2);
+    }
+}
@@ -2,9 +2,15 @@ use bevy::prelude::*;
 use systems::*;
 use pathfinder::*;
 
-use crate::systems::GameState;
+use components::{Civilian, Hunter, Projectile};
+use director::{update_director, Director};
+use heat::{assign_spawner_tiers, cool_heat, find_corpses, witness_kills, Heat};
+
+use crate::{core::{damage::DamageSystem, run::RunScopedApp}, player::visibility::update_player_visibility, systems::GameState};
 
 pub mod components;
+pub mod director;
+pub mod heat;
 mod pathfinder;
 pub mod systems;
 
@@ -15,8 +21,18 @@ impl Plugin for NPCPlugin {
         app
         // .add_systems(Startup, (spawn_civilian, spawn_hunter))
         .add_event::<Win>()
-        .add_systems(Update, (manage_civilians, manage_hunters, manage_projectiles,
-            process_collisions, entity_spawner, victory).run_if(in_state(GameState::InGame)))
+        .add_event::<Alarm>()
+        .run_scoped_resource(Director::default())
+        .run_scoped_resource(Heat::default())
+        .run_scoped_entities::<Civilian>()
+        .run_scoped_entities::<Hunter>()
+        .run_scoped_entities::<Projectile>()
+        // everything drawing from the GameRng runs in a fixed order, so a seed always plays out the same,
+        // and the heat is settled before the spawners read it
+        .add_systems(Update, ((cool_heat, witness_kills, find_corpses, manage_civilians, raise_alarm, manage_hunters, entity_spawner).chain().after(update_player_visibility).after(wound_npcs),
+            update_director.after(DamageSystem).before(manage_civilians), manage_projectiles,
+            process_collisions.before(DamageSystem), wound_npcs.after(DamageSystem), victory).run_if(in_state(GameState::Playing)))
+        .add_systems(Update, (assign_spawner_tiers, apply_hunter_tunables))
         ;
     }
 }
\ No newline at end of file
@@ -79,7 +79,7 @@ pub fn pathfinder(
                 if is_hunter {
                     if let Some(path) = find_path_huncha(&Pos(start_ipos), &Pos(end_ipos), trespassable) {
                         if path.len() > 5 {
-                            return Some(path[0..path.len() - 4].into_iter().map(|x| x.0).collect());
+                            return Some(path[0..path.len() - 4].iter().map(|x| x.0).collect());
                         } else {
                             return None;
                         }
@@ -144,7 +144,7 @@ fn find_path_hunesc(
 ) -> Option<Vec<Pos>>{
     if let Some(path) = astar(
     start,
-    |p| p.successors(&trespassable),
+    |p| p.successors(trespassable),
     |p| 9999 - p.0.distance_squared(end.0),
     |p| p.0.distance_squared(end.0) > 25)
     {
@@ -160,7 +160,7 @@ fn find_path_civesc(
 ) -> Option<Vec<Pos>>{
     if let Some(path) = astar(
     start,
-    |p| p.successors(&trespassable),
+    |p| p.successors(trespassable),
     |p| 9999 - p.0.distance_squared(end.0),
     |p| p.0.distance_squared(end.0) > 100)
     {
@@ -176,7 +176,7 @@ fn find_path_huncha(
 ) -> Option<Vec<Pos>>{
     if let Some(path) = astar(
     start,
-    |p| p.successors(&trespassable),
+    |p| p.successors(trespassable),
     |p| p.weight(end),
     |p| p.0.distance_squared(end.0) < 10)
     {
@@ -192,7 +192,7 @@ fn find_path_goto(
 ) -> Option<Vec<Pos>>{
     if let Some(path) = astar(
     start,
-    |p| p.successors(&trespassable),
+    |p| p.successors(trespassable),
     |p| p.weight(end),
     |p| p == end)
     {
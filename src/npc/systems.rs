@@ -1,35 +1,30 @@
 use std::{f32::consts::PI, time::Duration};
 
-use bevy::{color::palettes::css::{BLUE, RED}, math::uvec2, prelude::*};
+use bevy::{math::ivec2, prelude::*};
 use bevy_rapier2d::prelude::*;
-use rand::{thread_rng, Rng};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
 
 use crate::{
-    characters::animation::*, core::functions::TextureAtlasLayoutHandles, map::{plugin::{CivilianSpawner, CollectableRose, CollectableRoseSpawner, HunterSpawner, RespawnRosesEvent, TrespassableCells}, 
-    tilemap::{RaycastableHelp, Structure, TransformToGrid}}, player::{components::{HitPlayer, KillNpc, KillPlayer, Player}, systems::{PlayerController, BULLET_CG, NPC_CG, PLAYER_CG, RAYCASTABLE_STRUCT_CG, STRUCTURES_CG}}, sounds::components::PlaySoundEvent, stuff::{spawn_angry_particle, spawn_cililian_body, spawn_hunter_body, spawn_question_particle, spawn_warn_particle}, systems::DayCycle
+    characters::animation::*, core::{damage::{DamageEvent, DamageKind, DamageTaken, Damageable, Health, Resistances}, functions::TextureAtlasLayoutHandles, rng::GameRng, tunables::Tunables}, map::{plugin::{CivilianSpawner, CollectableRose, HunterSpawner, TrespassableCells}, 
+    tilemap::{RaycastableHelp, Structure, TransformToGrid}}, player::{abilities::BatForm, components::{KillNpc, KillPlayer, Player}, feeding::Feeding, visibility::PlayerVisibility, systems::{PlayerController, BULLET_CG, NPC_CG, PLAYER_CG, RAYCASTABLE_STRUCT_CG, STRUCTURES_CG}}, sounds::components::PlaySoundEvent, stuff::{spawn_angry_particle, Corpse, spawn_cililian_body, spawn_follow_blood_particle, spawn_hunter_body, spawn_question_particle, spawn_warn_particle}, systems::DayCycle
 };
 
-use super::{components::*, pathfinder};
-
-const SPOT_DIST: f32 = 200.0;
-const SPOT_DIST_CIV: f32 = 100.0;
-const THRESHOLD: f32 = 100.0;
-const UPP_THRESHOLD: f32 = THRESHOLD * 2.0;
-const CIV_MAXSPEED: f32 = 40.0;
-const CIV_ACCEL: f32 = 350.0;
-const PROJ_V: f32 = 150.0;
-const HUNTER_TIMER: f32 = 0.5;
-const HUNTER_MAXSPEED: f32 = 50.0;
-const HUNTER_ACCEL: f32 = 450.0;
+use super::{components::*, director::Director, heat::{Heat, Witnessed}, pathfinder};
 
 pub fn spawn_civilian(
-    mut commands: &mut Commands,
+    commands: &mut Commands,
     asset_server: &Res<AssetServer>,
     pos: Vec2,
     layout_handles: &mut ResMut<TextureAtlasLayoutHandles>,
-) {
-    let entity = spawn_civilian_animation_bundle(&mut commands, asset_server, layout_handles);
+    rng: &mut GameRng,
+    tunables: &Tunables,
+) -> Entity {
+    let entity = spawn_civilian_animation_bundle(commands, asset_server, layout_handles, rng);
     commands.entity(entity).insert((
+        Health::new(tunables.civilian_health),
+        Damageable {resistances: Resistances::none(), invulnerability: tunables.npc_invulnerability},
+    )).insert((
         TransformBundle::from_transform(Transform::from_translation(pos.extend(-2.))),
         RigidBody::Dynamic,
         Velocity::zero(),
@@ -48,13 +43,14 @@ pub fn spawn_civilian(
         AttackTimer {timer: Timer::new(Duration::from_secs_f32(0.5), TimerMode::Repeating)},
         ParticleTimer {timer: Timer::new(Duration::from_secs_f32(1.), TimerMode::Repeating)},
     ));
+    entity
 }
 
 pub fn manage_civilians(
     mut commands: Commands,
     mut civilians_data: Query<(&Transform, &mut Velocity, &mut NpcVelAccum, &mut NpcPath, &mut NpcState,
-        &mut ChillTimer, &mut AnimationController, &mut AttackTimer, &mut ParticleTimer, Entity), With<Civilian>>,
-    mut player_data: Query<(&Transform, Entity, &mut Player)>,
+        &mut ChillTimer, &mut AnimationController, &mut AttackTimer, &mut ParticleTimer, Entity, Has<Witnessed>), (With<Civilian>, Without<Held>)>,
+    mut player_data: Query<(&Transform, Entity, &mut Player, Has<BatForm>, Has<Feeding>)>,
     time: Res<Time>,
     day_cycle: Res<DayCycle>,
     transformer: Res<TransformToGrid>,
@@ -62,21 +58,24 @@ pub fn manage_civilians(
     rapier_context: Res<RapierContext>,
     mut layout_handles: ResMut<TextureAtlasLayoutHandles>,
     asset_server: Res<AssetServer>,
-    mut hit_player: EventWriter<HitPlayer>,
+    mut damage: EventWriter<DamageEvent>,
     mut play_sound: EventWriter<PlaySoundEvent>,
+    mut rng: ResMut<GameRng>,
+    mut alarm: EventWriter<Alarm>,
+    visibility: Res<PlayerVisibility>,
+    tunables: Res<Tunables>,
 ) {
-    if let Ok((player_transform, player_entity, mut player)) = player_data.get_single_mut() {
+    if let Ok((player_transform, player_entity, player, is_bat, is_feeding)) = player_data.get_single_mut() {
     if player.is_dead {return;}
     let player_pos = player_transform.translation.xy();
     let player_ipos = transformer.from_world_i32(player_pos);
     let dt = time.delta_seconds();
-    let mut rng = thread_rng();
     for (civ_transform, mut civ_controller,
         mut vel_accum , mut civ_path,
         mut civ_state, mut chill_timer,
         mut animation_controller,
         mut attack_timer, mut particle_timer,
-        civ_entity) in civilians_data.iter_mut() {
+        civ_entity, witnessed) in civilians_data.iter_mut() {
         let civ_pos = civ_transform.translation.xy();
         if civ_pos.distance(player_pos) > 1000. {
             continue;
@@ -86,9 +85,15 @@ pub fn manage_civilians(
         let length = direction.length();
         let mut player_in_sight = false;
         if let Some(last_seen_entity) = raycast(civ_pos, direction / length, length, &rapier_context) {
-        if last_seen_entity == player_entity && length < SPOT_DIST_CIV {
+        if last_seen_entity == player_entity && length < tunables.spot_dist_civ * visibility.0 {
             player_in_sight = true;
         }
+        // a witnessed feed sends everyone running and calls the hunters
+        if player_in_sight && is_feeding && *civ_state != NpcState::Dead && *civ_state != NpcState::Escape {
+            spawn_warn_particle(&mut commands, &mut layout_handles, &asset_server, &mut rng, civ_pos.extend(0.));
+            *civ_state = NpcState::Escape;
+            alarm.send(Alarm {witness: civ_pos, seen: player_pos, source: AlarmSource::Witness});
+        }
         // println!("{:?} {}", civ_state, player_in_sight);
         match *civ_state {
             NpcState::Look => {},
@@ -97,14 +102,16 @@ pub fn manage_civilians(
                 animation_controller.play_hurt();
                 commands.entity(civ_entity).remove::<Collider>();
                 if attack_timer.timer.finished() {
-                    spawn_cililian_body(&mut commands, &mut layout_handles, &asset_server, civ_pos.extend(0.));
+                    let body = spawn_cililian_body(&mut commands, &mut layout_handles, &asset_server, &mut rng, civ_pos.extend(0.));
+                    // the kill already raised the heat
+                    if witnessed {commands.entity(body).remove::<Corpse>();}
                     commands.entity(civ_entity).despawn_recursive();
                 }
             },
             NpcState::Attack => {
                 particle_timer.timer.tick(Duration::from_secs_f32(dt));
                 if particle_timer.timer.finished() {
-                    spawn_angry_particle(&mut commands, &mut layout_handles, &asset_server, civ_pos.extend(0.));
+                    spawn_angry_particle(&mut commands, &mut layout_handles, &asset_server, &mut rng, civ_pos.extend(0.));
                 }
                 if attack_timer.timer.elapsed_secs() == 0. {
                     play_sound.send(PlaySoundEvent::Hit);
@@ -112,8 +119,15 @@ pub fn manage_civilians(
                 }
                 attack_timer.timer.tick(Duration::from_secs_f32(dt));
                 if attack_timer.timer.finished() {
-                    if player_pos.distance(civ_pos) < 16. {
-                        hit_player.send(HitPlayer { dmg_type: 1,});
+                    // a bat is out of reach of pitchforks
+                    if player_pos.distance(civ_pos) < 16. && !is_bat {
+                        damage.send(DamageEvent {
+                            target: player_entity,
+                            source: Some(civ_entity),
+                            kind: DamageKind::Melee,
+                            amount: tunables.civilian_damage,
+                            knockback: (player_pos - civ_pos).normalize_or_zero() * tunables.civilian_knockback,
+                        });
                     }
                     *civ_state = NpcState::Chase;
                     attack_timer.timer.set_elapsed(Duration::from_secs(0))
@@ -128,16 +142,17 @@ pub fn manage_civilians(
                     if civ_path.path.is_none() {
                         chill_timer.timer.tick(Duration::from_secs_f32(dt));
                         if chill_timer.timer.finished() {
-                            let end = civ_ipos + IVec2::new(rng.gen_range(-2..2), rng.gen_range(-2..2));
+                            let end = civ_ipos + IVec2::new(rng.ai().gen_range(-2..2), rng.ai().gen_range(-2..2));
                             if trespassable.is_trespassable(&end) {
                                 civ_path.path = pathfinder(civ_ipos, end, &trespassable, &transformer, state, false);
                             }
                         }
                     }
                     if player_in_sight {
-                        spawn_warn_particle(&mut commands, &mut layout_handles, &asset_server, civ_pos.extend(0.));
+                        spawn_warn_particle(&mut commands, &mut layout_handles, &asset_server, &mut rng, civ_pos.extend(0.));
                         if day_cycle.is_night {
                             *civ_state = NpcState::Escape;
+                            alarm.send(Alarm {witness: civ_pos, seen: player_pos, source: AlarmSource::Fleeing});
                         } else {
                             *civ_state = NpcState::Chase;
                         }
@@ -155,16 +170,17 @@ pub fn manage_civilians(
                 } else { // chase
                     particle_timer.timer.tick(Duration::from_secs_f32(dt));
                     if particle_timer.timer.finished() {
-                        spawn_angry_particle(&mut commands, &mut layout_handles, &asset_server, civ_pos.extend(0.));
+                        spawn_angry_particle(&mut commands, &mut layout_handles, &asset_server, &mut rng, civ_pos.extend(0.));
                     }
                     animation_controller.arm();
                     civ_path.path = pathfinder(civ_ipos, player_ipos, &trespassable, &transformer, state, false);
                     if player_in_sight {
                         if day_cycle.is_night {
                             *civ_state = NpcState::Escape;
+                            alarm.send(Alarm {witness: civ_pos, seen: player_pos, source: AlarmSource::Fleeing});
                         }
                     } else {
-                        spawn_question_particle(&mut commands, &mut layout_handles, &asset_server, civ_pos.extend(0.));
+                        spawn_question_particle(&mut commands, &mut layout_handles, &asset_server, &mut rng, civ_pos.extend(0.));
                         *civ_state = NpcState::Chill;
                         civ_path.path = None;
                     }
@@ -205,14 +221,14 @@ pub fn manage_civilians(
                     } else {
                         animation_controller.play_idle_priority(1);
                     }
-                    vel_accum.v = vel_accum.v.move_towards(move_dir.normalize_or_zero() * CIV_MAXSPEED, dt * CIV_ACCEL);
-                    if vel_accum.v.length() > CIV_MAXSPEED {
-                        vel_accum.v = vel_accum.v.normalize() * CIV_MAXSPEED
+                    vel_accum.v = vel_accum.v.move_towards(move_dir.normalize_or_zero() * tunables.civ_max_speed, dt * tunables.civ_accel);
+                    if vel_accum.v.length() > tunables.civ_max_speed {
+                        vel_accum.v = vel_accum.v.normalize() * tunables.civ_max_speed
                     }
                     civ_controller.linvel = vel_accum.v;
                 } else {
                     civ_controller.linvel = Vec2::ZERO;
-                    if civ_pos.distance(player_pos) > THRESHOLD {
+                    if civ_pos.distance(player_pos) > tunables.threshold {
                         *civ_state = NpcState::Chill
                     } else {
                         if player_in_sight {
@@ -243,9 +259,13 @@ pub fn spawn_hunter(
     asset_server: &Res<AssetServer>,
     pos: Vec2,
     layout_handles: &mut ResMut<TextureAtlasLayoutHandles>,
-) {
+    tunables: &Tunables,
+) -> Entity {
     let entity = spawn_hunter_animation_bundle(commands, asset_server, layout_handles);
     commands.entity(entity).insert((
+        Health::new(tunables.hunter_health),
+        Damageable {resistances: Resistances::none(), invulnerability: tunables.npc_invulnerability},
+    )).insert((
         (
             Name::new("Hunter"),
             RigidBody::Dynamic,
@@ -263,11 +283,29 @@ pub fn spawn_hunter(
             Group::from_bits(NPC_CG).unwrap(),
             Group::from_bits(PLAYER_CG | RAYCASTABLE_STRUCT_CG  | STRUCTURES_CG).unwrap(),
         ),
-        HunterTimer { timer: Timer::new(Duration::from_secs_f32(HUNTER_TIMER), TimerMode::Repeating) },
+        HunterTimer { timer: Timer::new(Duration::from_secs_f32(tunables.hunter_throw_cd), TimerMode::Repeating) },
         NpcState::Chill,
         ChillTimer {timer: Timer::new(Duration::from_secs(1), TimerMode::Repeating)},
         PlayerLastPos {pos: IVec2::ZERO},
+        ShoutTimer::new(tunables.squad_shout_cd),
     ));
+    entity
+}
+
+/// Hunters already out keep their progress to the next throw when the cooldown is retuned.
+pub fn apply_hunter_tunables(
+    tunables: Res<Tunables>,
+    mut timers: Query<&mut HunterTimer>,
+) {
+    if !tunables.is_changed() {return}
+    let duration = Duration::from_secs_f32(tunables.hunter_throw_cd);
+    for mut hunter_timer in timers.iter_mut() {
+        let timer = &mut hunter_timer.timer;
+        if timer.duration() == duration {continue}
+        let progress = timer.fraction();
+        timer.set_duration(duration);
+        timer.set_elapsed(duration.mul_f32(progress));
+    }
 }
 
 pub fn manage_hunters(
@@ -275,7 +313,7 @@ pub fn manage_hunters(
     asset_server: Res<AssetServer>,
     mut hunters_data: Query<(&Transform, &mut Velocity,
         &mut NpcVelAccum, &mut NpcPath, &mut HunterTimer, &mut NpcState,
-        &mut ChillTimer, &mut AnimationController, &mut PlayerLastPos, Entity), Without<Player>>,
+        &mut ChillTimer, &mut AnimationController, &mut PlayerLastPos, &mut ShoutTimer, Entity, Has<Witnessed>), Without<Player>>,
     player_data: Query<(&Transform, &PlayerController, Entity, &Player)>,
     transformer: Res<TransformToGrid>,
     trespassable: Res<TrespassableCells>,
@@ -283,9 +321,17 @@ pub fn manage_hunters(
     time: Res<Time>,
     mut atlas_handles: ResMut<TextureAtlasLayoutHandles>,
     mut play_sound: EventWriter<PlaySoundEvent>,
+    mut rng: ResMut<GameRng>,
+    mut alarm: EventWriter<Alarm>,
+    visibility: Res<PlayerVisibility>,
+    director: Res<Director>,
+    tunables: Res<Tunables>,
 ) {
     if let Ok(player_data) = player_data.get_single() {
     if player_data.3.is_dead {return;}
+    let max_speed = tunables.hunter_max_speed * director.hunter_speed(&tunables);
+    let accel = tunables.hunter_accel * director.hunter_speed(&tunables);
+    let proj_v = director.proj_v(&tunables);
     let player_pos = player_data.0.translation.xy();
     let player_ipos = transformer.from_world_i32(player_pos);
     let player_vel = player_data.1.accumulated_velocity;
@@ -295,7 +341,7 @@ pub fn manage_hunters(
         mut vel_accum , mut hunter_path,
         mut hunter_timer, mut hunter_state, mut chill_timer,
         mut animation_controller, mut player_last_pos,
-        hunter_entity) in hunters_data.iter_mut() {
+        mut shout_timer, hunter_entity, witnessed) in hunters_data.iter_mut() {
         hunter_controller.linvel = Vec2::ZERO;
         let hunter_pos = hunter_transform.translation.xy();
         let hunter_ipos = transformer.from_world_i32(hunter_pos);
@@ -306,13 +352,19 @@ pub fn manage_hunters(
         let length = direction.length();
         let mut player_in_sight = false;
         if let Some(last_seen_entity) = raycast(hunter_pos, direction / length, length, &rapier_context) {
-        if last_seen_entity == player_entity && length < SPOT_DIST{
+        if last_seen_entity == player_entity && length < tunables.spot_dist * director.sight(&tunables) * visibility.0 {
             player_in_sight = true;
         }
+        // tell the squad where the player is
+        shout_timer.timer.tick(Duration::from_secs_f32(dt));
+        if player_in_sight && *hunter_state != NpcState::Dead && shout_timer.timer.finished() {
+            alarm.send(Alarm {witness: hunter_pos, seen: player_pos, source: AlarmSource::Hunter});
+            shout_timer.timer.reset();
+        }
 
         match *hunter_state {
             NpcState::Attack => {
-                hunter_timer.timer.tick(Duration::from_secs_f32(dt));
+                hunter_timer.timer.tick(Duration::from_secs_f32(dt * director.throw_rate(&tunables)));
                 let dir = player_pos - hunter_pos;
                 if dir.x.abs() > dir.y.abs() {
                     if dir.x > 0. {
@@ -331,10 +383,10 @@ pub fn manage_hunters(
                 if hunter_timer.timer.finished() {
                 animation_controller.play_hunter_throw();
                 play_sound.send(PlaySoundEvent::Throw);
-                if let Some(intercept) = calculate_intercept(hunter_pos, player_pos, player_vel, PROJ_V) {
+                if let Some(intercept) = calculate_intercept(hunter_pos, player_pos, player_vel, proj_v) {
                     let dir = intercept - hunter_pos;
                     let dir = dir / dir.length();
-                    let throwable_variant = rand::thread_rng().gen_range(0..4);
+                    let throwable_variant = rng.cosmetic().gen_range(0..4);
                     
 
 
@@ -348,7 +400,7 @@ pub fn manage_hunters(
                         ),
                         LockedAxes::ROTATION_LOCKED_Z,
                         Velocity {
-                            linvel: PROJ_V * dir,
+                            linvel: proj_v * dir,
                             angvel: 0.0,
                         },
                         DespawnTimer { timer: Timer::new(Duration::from_secs(6), TimerMode::Once) },
@@ -376,9 +428,9 @@ pub fn manage_hunters(
                 }
             }
             let dist = player_pos.distance(hunter_pos);
-            if dist < THRESHOLD {
+            if dist < tunables.threshold {
                 *hunter_state = NpcState::Escape;
-            } else if dist < UPP_THRESHOLD {
+            } else if dist < tunables.threshold * 2. {
                 *hunter_state = NpcState::Chase;
             }
             } else {
@@ -391,7 +443,8 @@ pub fn manage_hunters(
                 animation_controller.play_hurt();
                 commands.entity(hunter_entity).remove::<Collider>();
                 if hunter_timer.timer.finished() {
-                    spawn_hunter_body(&mut commands, &mut atlas_handles, &asset_server, hunter_pos.extend(0.));
+                    let body = spawn_hunter_body(&mut commands, &mut atlas_handles, &asset_server, &mut rng, hunter_pos.extend(0.));
+                    if witnessed {commands.entity(body).remove::<Corpse>();}
                     commands.entity(hunter_entity).despawn_recursive();
                 }
             }
@@ -400,13 +453,12 @@ pub fn manage_hunters(
                     animation_controller.play_idle_priority(1);
                     if player_in_sight {
                         *hunter_state = NpcState::Chase;
-                        spawn_warn_particle(&mut commands, &mut atlas_handles, &asset_server, hunter_pos.extend(0.));
+                        spawn_warn_particle(&mut commands, &mut atlas_handles, &asset_server, &mut rng, hunter_pos.extend(0.));
                     } else {
                         if hunter_path.path.is_none() {
-                            let mut rng = thread_rng();
                             chill_timer.timer.tick(Duration::from_secs_f32(dt));
                             if chill_timer.timer.finished() {
-                                let end = hunter_ipos + IVec2::new(rng.gen_range(-2..2), rng.gen_range(-2..2));
+                                let end = hunter_ipos + IVec2::new(rng.ai().gen_range(-2..2), rng.ai().gen_range(-2..2));
                                 if trespassable.is_trespassable(&end) {
                                     hunter_path.path = pathfinder(hunter_ipos, end, &trespassable, &transformer, state, true);
                                 }
@@ -416,11 +468,11 @@ pub fn manage_hunters(
                 } else if state == NpcState::Look {
                     if player_in_sight {
                         *hunter_state = NpcState::Chase;
-                        spawn_warn_particle(&mut commands, &mut atlas_handles, &asset_server, hunter_pos.extend(0.));
+                        spawn_warn_particle(&mut commands, &mut atlas_handles, &asset_server, &mut rng, hunter_pos.extend(0.));
                     } else {
                         hunter_path.path = pathfinder(hunter_ipos, player_last_pos.pos, &trespassable, &transformer, state, true);
                         if hunter_path.path.is_none() {
-                            spawn_question_particle(&mut commands, &mut atlas_handles, &asset_server, hunter_pos.extend(0.));
+                            spawn_question_particle(&mut commands, &mut atlas_handles, &asset_server, &mut rng, hunter_pos.extend(0.));
                             *hunter_state = NpcState::Chill;
                         }
                     }
@@ -468,9 +520,9 @@ pub fn manage_hunters(
                         animation_controller.play_idle_priority(1);
                     }
                     
-                    vel_accum.v = vel_accum.v.move_towards(move_dir.normalize_or_zero() * HUNTER_MAXSPEED, dt * HUNTER_ACCEL);
-                    if vel_accum.v.length() > HUNTER_MAXSPEED {
-                        vel_accum.v = vel_accum.v.normalize() * HUNTER_MAXSPEED
+                    vel_accum.v = vel_accum.v.move_towards(move_dir.normalize_or_zero() * max_speed, dt * accel);
+                    if vel_accum.v.length() > max_speed {
+                        vel_accum.v = vel_accum.v.normalize() * max_speed
                     }
                     hunter_controller.linvel = vel_accum.v;
                 }
@@ -495,7 +547,7 @@ fn calculate_intercept(shooter_pos: Vec2, target_pos: Vec2, target_vel: Vec2, pr
         return None;
     }
     let i = target_pos + target_vel * t;
-    return Some(i);
+    Some(i)
 }
 
 pub fn manage_projectiles(
@@ -512,7 +564,7 @@ pub fn manage_projectiles(
     }
 }
 
-#[derive(Resource)]
+#[derive(Resource, Clone, Debug, Serialize, Deserialize)]
 pub struct RosesCollected {
     pub collected: u32,
     pub max: u32,
@@ -521,51 +573,57 @@ pub struct RosesCollected {
 pub fn process_collisions(
     mut commands: Commands,
     mut collision_events: EventReader<CollisionEvent>,
-    mut player: Query<(Entity, &Player)>,
-    mut hunters: Query<&mut NpcState, (With<Hunter>, Without<Civilian>)>,
-    mut civilians: Query<&mut NpcState, With<Civilian>>,
-    projectiles: Query<&Projectile>,
+    mut player: Query<(Entity, &Player, &Transform, &Velocity)>,
+    hunters: Query<&Transform, (With<Hunter>, Without<Civilian>)>,
+    projectiles: Query<&Velocity, With<Projectile>>,
     structures: Query<&Structure>,
     help: Query<&RaycastableHelp>,
     roses: Query<Entity, With<CollectableRose>>,
     mut roses_collected: ResMut<RosesCollected>,
     day_cycle: Res<DayCycle>,
-    mut hit_player: EventWriter<HitPlayer>,
-    mut kill_npc: EventWriter<KillNpc>,
-    mut play_sound: EventWriter<PlaySoundEvent>,
-    mut win: EventWriter<Win>
+    mut damage: EventWriter<DamageEvent>,
+    mut win: EventWriter<Win>,
+    tunables: Res<Tunables>,
 ) {
-    if let Ok((player_entity, player)) = player.get_single_mut() {
+    if let Ok((player_entity, player, player_transform, player_velocity)) = player.get_single_mut() {
+        let bite = |target: Entity| DamageEvent {
+            target,
+            source: Some(player_entity),
+            kind: DamageKind::Bite,
+            amount: tunables.bite_damage,
+            knockback: player_velocity.linvel.normalize_or_zero() * tunables.bite_knockback,
+        };
         for collision_event in collision_events.read() {
             if let CollisionEvent::Started(reciever_entity, sender_entity, _) = collision_event {
                 // player appears to always be reciever
                 let sender_entity = *sender_entity;
-                if let Ok(_) = projectiles.get(sender_entity) {
+                if let Ok(velocity) = projectiles.get(sender_entity) {
                     if *reciever_entity == player_entity {
-                        hit_player.send(HitPlayer { dmg_type: 0});
+                        damage.send(DamageEvent {
+                            target: player_entity,
+                            source: Some(sender_entity),
+                            kind: DamageKind::Projectile,
+                            amount: tunables.projectile_damage,
+                            knockback: velocity.linvel.normalize_or_zero() * tunables.projectile_knockback,
+                        });
                     }
                     commands.entity(sender_entity).despawn_recursive();
-                } else if let Ok(mut state) = civilians.get_mut(sender_entity) {
-                    if day_cycle.is_night {
-                        // kill civilian
-                        *state = NpcState::Dead;
-                        kill_npc.send(KillNpc { npc_type: 0 });
-                        play_sound.send(PlaySoundEvent::Kill);
-                    }
-                } else if let Ok(mut state) = hunters.get_mut(sender_entity) {
+                } else if let Ok(hunter_transform) = hunters.get(sender_entity) {
                     if day_cycle.is_night {
-                        // kill hunter
-                        *state = NpcState::Dead;
-                        kill_npc.send(KillNpc { npc_type: 1 });
-                        play_sound.send(PlaySoundEvent::Kill);
+                        damage.send(bite(sender_entity));
                     } else {
                         if *reciever_entity == player_entity {
-                            hit_player.send(HitPlayer { dmg_type: 2});
+                            let away = player_transform.translation.xy() - hunter_transform.translation.xy();
+                            damage.send(DamageEvent {
+                                target: player_entity,
+                                source: Some(sender_entity),
+                                kind: DamageKind::Holy,
+                                amount: tunables.hunter_damage,
+                                knockback: away.normalize_or_zero() * tunables.hunter_knockback,
+                            });
                         }
                     }
-                } else if let Ok(_) = structures.get(sender_entity) {
-                    commands.entity(player_entity).remove::<Sensor>();
-                } else if let Ok(_) = help.get(sender_entity) {
+                } else if structures.get(sender_entity).is_ok() || help.get(sender_entity).is_ok() {
                     commands.entity(player_entity).remove::<Sensor>();
                 } else if let Ok(rose_entity) = roses.get(sender_entity) {
                     if player.is_dead {return;}
@@ -580,19 +638,123 @@ pub fn process_collisions(
     }
 }
 
+/// Kills, or bleeds and scares off whoever survived a bite.
+pub fn wound_npcs(
+    mut commands: Commands,
+    mut taken: EventReader<DamageTaken>,
+    mut npcs: Query<(&mut NpcState, &mut AnimationController, &Transform, Has<Hunter>), Or<(With<Civilian>, With<Hunter>)>>,
+    mut layout_handles: ResMut<TextureAtlasLayoutHandles>,
+    asset_server: Res<AssetServer>,
+    mut kill_npc: EventWriter<KillNpc>,
+    mut play_sound: EventWriter<PlaySoundEvent>,
+    healths: Query<&Health>,
+) {
+    for hit in taken.read() {
+        let Ok((mut state, mut animation_controller, transform, is_hunter)) = npcs.get_mut(hit.target) else {continue};
+        if *state == NpcState::Dead {continue}
+        if hit.killed {
+            *state = NpcState::Dead;
+            kill_npc.send(KillNpc { npc: hit.target, npc_type: if is_hunter {1} else {0}, drained: false, pos: transform.translation.xy() });
+            play_sound.send(PlaySoundEvent::Kill);
+            continue;
+        }
+        animation_controller.play_hurt();
+        play_sound.send(PlaySoundEvent::Hit);
+        let lost = healths.get(hit.target).map(|h| 1. - h.fraction()).unwrap_or(1.);
+        let length = (lost * 5.).ceil() as usize;
+        spawn_follow_blood_particle(&mut commands, &mut layout_handles, &asset_server, hit.target, transform.translation, length);
+        *state = NpcState::Escape;
+    }
+}
+
 #[derive(Event)]
 pub struct Win;
 
+/// Who raised an [`Alarm`], it decides which hunters hear it.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum AlarmSource {
+    /// A civilian saw a feed, the hunters around come.
+    Witness,
+    /// A civilian running from the player at night, tells the closest hunter.
+    Fleeing,
+    /// A hunter calls its squad, those around or in sight of it come.
+    Hunter,
+}
+
+/// Someone at `witness` saw the player at `seen`.
+#[derive(Event)]
+pub struct Alarm {
+    pub witness: Vec2,
+    pub seen: Vec2,
+    pub source: AlarmSource,
+}
+
+/// Idle and searching hunters who hear an alarm go look where the player was seen.
+/// They spread around the spot, so a squad closes in from several sides instead of
+/// queuing up on the same path.
+pub fn raise_alarm(
+    mut commands: Commands,
+    mut alarms: EventReader<Alarm>,
+    mut hunters: Query<(Entity, &Transform, &mut NpcState, &mut PlayerLastPos), With<Hunter>>,
+    transformer: Res<TransformToGrid>,
+    trespassable: Res<TrespassableCells>,
+    rapier_context: Res<RapierContext>,
+    mut layout_handles: ResMut<TextureAtlasLayoutHandles>,
+    asset_server: Res<AssetServer>,
+    mut rng: ResMut<GameRng>,
+    tunables: Res<Tunables>,
+) {
+    for alarm in alarms.read() {
+        let mut listeners: Vec<(Entity, Vec2)> = hunters.iter()
+            .filter(|(_, _, state, _)| matches!(**state, NpcState::Chill | NpcState::Look))
+            .map(|(entity, transform, _, _)| (entity, transform.translation.xy()))
+            .collect();
+        listeners.sort_by(|a, b| a.1.distance(alarm.seen).total_cmp(&b.1.distance(alarm.seen)));
+        match alarm.source {
+            AlarmSource::Witness => listeners.retain(|(_, pos)| pos.distance(alarm.witness) <= tunables.alarm_radius),
+            AlarmSource::Fleeing => {
+                let nearest = listeners.iter().copied()
+                    .min_by(|a, b| a.1.distance(alarm.witness).total_cmp(&b.1.distance(alarm.witness)));
+                listeners = nearest.into_iter().collect();
+            }
+            AlarmSource::Hunter => listeners.retain(|(_, pos)| {
+                let dist = pos.distance(alarm.witness);
+                dist <= tunables.alarm_radius
+                    || (dist <= tunables.squad_sight_range && raycast(*pos, (alarm.witness - *pos) / dist, dist, &rapier_context).is_none())
+            }),
+        }
+        let seen = transformer.from_world_i32(alarm.seen);
+        for (slot, (entity, pos)) in listeners.into_iter().enumerate() {
+            let Ok((_, _, mut state, mut last_pos)) = hunters.get_mut(entity) else {continue};
+            if *state == NpcState::Chill {
+                spawn_warn_particle(&mut commands, &mut layout_handles, &asset_server, &mut rng, pos.extend(0.));
+            }
+            *state = NpcState::Look;
+            last_pos.pos = flank_goal(seen, transformer.from_world_i32(pos), slot, &trespassable, &tunables);
+        }
+    }
+}
+
+/// The closest hunter comes straight at the spot, the next ones from its sides, then from behind.
+/// Falls back to the spot itself when there is no walkable cell around the flank.
+fn flank_goal(seen: IVec2, hunter: IVec2, slot: usize, trespassable: &TrespassableCells, tunables: &Tunables) -> IVec2 {
+    const FLANKS: [f32; 4] = [0., PI * 0.5, -PI * 0.5, PI];
+    if slot == 0 {return seen}
+    let from = (hunter - seen).as_vec2().try_normalize().unwrap_or(Vec2::X);
+    let dir = Vec2::from_angle(FLANKS[slot % FLANKS.len()]).rotate(from);
+    let goal = seen + (dir * tunables.flank_distance).round().as_ivec2();
+    (0..=2).flat_map(|r| (-r..=r).flat_map(move |x| (-r..=r).map(move |y| ivec2(x, y))))
+        .map(|offset| goal + offset)
+        .find(|cell| trespassable.is_trespassable(cell))
+        .unwrap_or(seen)
+}
+
 pub fn victory(
     mut win: EventReader<Win>,
     mut kill_player: EventWriter<KillPlayer>,
-    mut roses_collected: ResMut<RosesCollected>,
-    mut event: EventWriter<RespawnRosesEvent>,
 ) {
     for _ in win.read() {
         kill_player.send(KillPlayer { won: true });
-        roses_collected.collected = 0;
-        event.send(RespawnRosesEvent);
     }
 }
 
@@ -606,34 +768,52 @@ pub fn entity_spawner(
     asset_server: Res<AssetServer>,
     time: Res<Time>,
     day_cycle: Res<DayCycle>,
+    mut rng: ResMut<GameRng>,
+    mut director: ResMut<Director>,
+    heat: Res<Heat>,
+    tunables: Res<Tunables>,
 ) {
     let dt = time.delta_seconds();
-    let mut rand = thread_rng();
+    let spawn_chance = tunables.spawn_chance.clamp(0., 1.);
+    let hunter_chance = director.spawn_chance(&tunables);
+    let tier = heat.tier(&tunables);
+    let chase_chance = (tier as f64 * tunables.heat_chase_chance).clamp(0., 1.);
+    let mut hunter_count = hunters.iter().len();
     for (mut spawner, spawner_gpos) in civilian_spawners.iter_mut() {
         spawner.timer.tick(Duration::from_secs_f32(dt));
         if spawner.timer.finished() {
             let spawner_pos = spawner_gpos.translation().xy();
-            if rand.gen_bool(0.15) {
-                if civilians.iter().len() < 200 && !day_cycle.is_night{
-                    spawn_civilian(&mut commands, &asset_server, spawner_pos, &mut layout_handles);
-                }
+            if rng.spawning().gen_bool(spawn_chance)
+                && civilians.iter().len() < tunables.max_civilians && !day_cycle.is_night {
+                spawn_civilian(&mut commands, &asset_server, spawner_pos, &mut layout_handles, &mut rng, &tunables);
             }
         }
     }
     for (mut spawner, spawner_gpos) in hunter_spawners.iter_mut() {
+        if spawner.heat_tier > tier {continue}
         spawner.timer.tick(Duration::from_secs_f32(dt));
         if spawner.timer.finished() {
             let spawner_pos = spawner_gpos.translation().xy();
-            if rand.gen_bool(0.15) {
-                if hunters.iter().len() < 200 && day_cycle.is_night{
-                    spawn_hunter(&mut commands, &asset_server, spawner_pos, &mut layout_handles);
+            if rng.spawning().gen_bool(hunter_chance) && hunter_count < tunables.max_hunters {
+                if day_cycle.is_night && director.budget > 0 {
+                    let hunter = spawn_hunter(&mut commands, &asset_server, spawner_pos, &mut layout_handles, &tunables);
+                    // the village is out looking, these go straight for the player
+                    if rng.spawning().gen_bool(chase_chance) {
+                        commands.entity(hunter).insert(NpcState::Chase);
+                    }
+                    director.budget -= 1;
+                    hunter_count += 1;
+                } else if !day_cycle.is_night && hunter_count < tier * tunables.heat_patrol_size {
+                    // a wary village keeps a patrol out by day
+                    spawn_hunter(&mut commands, &asset_server, spawner_pos, &mut layout_handles, &tunables);
+                    hunter_count += 1;
                 }
             }
         }
     }
 }
 
-fn raycast(
+pub(super) fn raycast(
     origin: Vec2,
     dir: Vec2,
     max_toi: f32,
@@ -648,8 +828,50 @@ fn raycast(
     if let Some((entity, _)) = rapier_context.cast_ray(
         origin, dir, max_toi, solid, filter
     ) {
-        return Some(entity);
+        Some(entity)
     } else {
-        return None;
+        None
+    }
+}
+#[cfg(test)]
+mod tests {
+    use bevy::math::vec2;
+
+    use super::*;
+
+    fn grid(walkable: bool) -> TrespassableCells {
+        TrespassableCells {cells: vec![vec![walkable; 20]; 20], ..default()}
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn flank_goals_differ_per_slot() {
+        let tunables = Tunables::default();
+        let trespassable = grid(true);
+        let seen = ivec2(10, 10);
+        // coming from the west
+        let hunter = ivec2(0, 10);
+        let goals: Vec<IVec2> = (0..4).map(|slot| flank_goal(seen, hunter, slot, &trespassable, &tunables)).collect();
+        assert_eq!(goals, vec![seen, ivec2(10, 6), ivec2(10, 14), ivec2(14, 10)]);
+    }
+
+    #[test]
+    fn flank_goal_avoids_blocked_cells() {
+        let tunables = Tunables::default();
+        let mut trespassable = grid(true);
+        trespassable.cells[10][6] = false;
+        let goal = flank_goal(ivec2(10, 10), ivec2(0, 10), 1, &trespassable, &tunables);
+        assert_ne!(goal, ivec2(10, 6));
+        assert!(trespassable.is_trespassable(&goal));
+        assert!(goal.as_vec2().distance(vec2(10., 6.)) <= 2f32.sqrt());
+    }
+
+    #[test]
+    fn flank_goal_falls_back_to_seen() {
+        let tunables = Tunables::default();
+        let trespassable = grid(false);
+        let seen = ivec2(10, 10);
+        for slot in 0..4 {
+            assert_eq!(flank_goal(seen, ivec2(0, 10), slot, &trespassable, &tunables), seen);
+        }
+    }
+}
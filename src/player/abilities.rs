@@ -0,0 +1,351 @@
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{characters::animation::AnimationController, core::{damage::Health, functions::TextureAtlasLayoutHandles, input::Action, rng::GameRng, tunables::Tunables}, sounds::components::PlaySoundEvent, stuff::spawn_bat_particle, systems::DayCycle};
+
+use super::{coffin::Slumber, components::{Player, PlayerInput}, systems::{PlayerController, BULLET_CG, NPC_CG, PLAYER_CG, RAYCASTABLE_STRUCT_CG, STRUCTURES_CG}};
+
+/// Seconds of a dash at `dash_tick` 1.
+const DASH_DURATION: f32 = 0.35;
+/// Distance between the rings searched for a free spot to land a bat on.
+const LAND_STEP: f32 = 4.;
+const LAND_RINGS: usize = 8;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum AbilityId {
+    Dash,
+    BatForm,
+}
+
+/// On the player while it is a bat: faster, flies over low obstacles, ignored by civilians.
+#[derive(Component)]
+pub struct BatForm;
+
+/// What an ability can touch while it runs.
+pub struct AbilityContext<'a, 'w, 's> {
+    pub commands: &'a mut Commands<'w, 's>,
+    pub entity: Entity,
+    pub player: &'a Player,
+    pub health: &'a mut Health,
+    pub controller: &'a mut PlayerController,
+    pub velocity: &'a mut Velocity,
+    pub animation: &'a mut AnimationController,
+    pub day_cycle: &'a DayCycle,
+    pub tunables: &'a Tunables,
+    /// Movement input at the moment of activation.
+    pub direction: Vec2,
+    pub dt: f32,
+}
+
+pub struct Ability {
+    pub id: AbilityId,
+    pub name: &'static str,
+    /// Health spent on activation, it can't be used with less.
+    pub cost: fn(&Tunables) -> f32,
+    /// Seconds from one activation to the next.
+    pub cooldown: fn(&Player, &Tunables) -> f32,
+    /// Walking (and hunger) pause while it is active.
+    pub moves: bool,
+    /// Collision groups the player passes through while it is active.
+    pub ignores: fn(&DayCycle) -> u32,
+    pub sound: Option<PlaySoundEvent>,
+    /// Returning false cancels the activation, nothing is spent then.
+    pub activate: fn(&mut AbilityContext) -> bool,
+    /// Runs every frame with the seconds since activation, returns false once it is over.
+    pub tick: fn(&mut AbilityContext, f32) -> bool,
+    pub end: fn(&mut AbilityContext),
+}
+
+#[derive(Resource)]
+pub struct AbilityRegistry {
+    pub abilities: Vec<Ability>,
+}
+
+impl Default for AbilityRegistry {
+    fn default() -> Self {
+        AbilityRegistry {abilities: vec![
+            Ability {
+                id: AbilityId::Dash,
+                name: "Dash",
+                cost: |_| 0.,
+                cooldown: |p, _| p.dash_cd,
+                moves: true,
+                // by day npcs are dashed through, at night they are bitten
+                ignores: |day| if day.is_night {BULLET_CG} else {BULLET_CG | NPC_CG},
+                sound: Some(PlaySoundEvent::Dash),
+                activate: dash_activate,
+                tick: dash_tick,
+                end: dash_end,
+            },
+            Ability {
+                id: AbilityId::BatForm,
+                name: "Bat Form",
+                cost: |t| t.bat_cost,
+                cooldown: |_, t| t.bat_cooldown,
+                moves: false,
+                // flies over fences and graves, walls and houses still block
+                ignores: |_| STRUCTURES_CG,
+                sound: Some(PlaySoundEvent::Dash),
+                activate: bat_activate,
+                tick: bat_tick,
+                end: bat_end,
+            },
+        ]}
+    }
+}
+
+impl AbilityRegistry {
+    pub fn get(&self, id: AbilityId) -> Option<&Ability> {
+        self.abilities.iter().find(|a| a.id == id)
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct AbilitySlot {
+    pub ability: AbilityId,
+    pub action: Action,
+    /// Seconds until it can be used again.
+    pub cooldown: f32,
+    /// Seconds since activation while it is active.
+    pub active: Option<f32>,
+    pub direction: Vec2,
+}
+
+impl AbilitySlot {
+    pub fn new(ability: AbilityId, action: Action) -> Self {
+        AbilitySlot {ability, action, cooldown: 0., active: None, direction: Vec2::ZERO}
+    }
+}
+
+/// Every run starts with the dash, the other slots are unlocked by upgrades.
+#[derive(Component, Clone, Debug, Serialize, Deserialize)]
+pub struct Abilities {
+    pub slots: Vec<AbilitySlot>,
+}
+
+impl Default for Abilities {
+    fn default() -> Self {
+        Abilities {slots: vec![AbilitySlot::new(AbilityId::Dash, Action::Dash)]}
+    }
+}
+
+impl Abilities {
+    pub fn has(&self, ability: AbilityId) -> bool {
+        self.slots.iter().any(|s| s.ability == ability)
+    }
+
+    /// Binds the ability to the first free slot action, does nothing without one.
+    pub fn unlock(&mut self, ability: AbilityId) {
+        if self.has(ability) {return}
+        let free = Action::ABILITY_SLOTS.into_iter().find(|a| !self.slots.iter().any(|s| s.action == *a));
+        if let Some(action) = free {
+            self.slots.push(AbilitySlot::new(ability, action));
+        }
+    }
+
+    pub fn is_active(&self, ability: AbilityId) -> bool {
+        self.slots.iter().any(|s| s.ability == ability && s.active.is_some())
+    }
+
+    pub fn is_any_active(&self) -> bool {
+        self.slots.iter().any(|s| s.active.is_some())
+    }
+
+    pub fn is_moving(&self, registry: &AbilityRegistry) -> bool {
+        self.slots.iter().any(|s| s.active.is_some() && registry.get(s.ability).is_some_and(|a| a.moves))
+    }
+
+    pub fn ignored_groups(&self, registry: &AbilityRegistry, day_cycle: &DayCycle) -> u32 {
+        self.slots.iter()
+            .filter(|s| s.active.is_some())
+            .filter_map(|s| registry.get(s.ability))
+            .fold(0, |ignored, a| ignored | (a.ignores)(day_cycle))
+    }
+
+    /// Stops everything without running the ends, for a fresh player.
+    pub fn reset(&mut self) {
+        for slot in self.slots.iter_mut() {
+            slot.cooldown = 0.;
+            slot.active = None;
+        }
+    }
+}
+
+#[derive(Event)]
+pub struct AbilityUsed {
+    pub ability: AbilityId,
+}
+
+#[derive(Event)]
+pub struct AbilityEnded {
+    pub ability: AbilityId,
+}
+
+/// What the player collides with, minus the groups in `ignored`.
+pub fn player_collision_groups(ignored: u32) -> CollisionGroups {
+    CollisionGroups::new(
+        Group::from_bits(PLAYER_CG).unwrap(),
+        Group::from_bits((BULLET_CG | STRUCTURES_CG | NPC_CG | RAYCASTABLE_STRUCT_CG) & !ignored).unwrap()
+    )
+}
+
+/// Runs after `player_controller`, so a moving ability has the last word on the velocity.
+pub fn update_abilities(
+    mut commands: Commands,
+    mut player_q: Query<(Entity, &Player, &mut Abilities, &mut Health, &mut PlayerController, &mut Velocity, &mut AnimationController, &CollisionGroups), Without<Slumber>>,
+    registry: Res<AbilityRegistry>,
+    input: Res<PlayerInput>,
+    day_cycle: Res<DayCycle>,
+    tunables: Res<Tunables>,
+    time: Res<Time>,
+    mut play_sound: EventWriter<PlaySoundEvent>,
+    mut used: EventWriter<AbilityUsed>,
+    mut ended: EventWriter<AbilityEnded>,
+) {
+    let Ok((entity, player, mut abilities, mut health, mut controller, mut velocity, mut animation, groups)) = player_q.get_single_mut() else {return};
+    if player.is_dead {return}
+    let dt = time.delta_seconds();
+    let mut changed = false;
+    for slot in abilities.slots.iter_mut() {
+        let Some(ability) = registry.get(slot.ability) else {continue};
+        slot.cooldown = (slot.cooldown - dt).max(0.);
+        let mut ctx = AbilityContext {
+            commands: &mut commands,
+            entity,
+            player,
+            health: &mut health,
+            controller: &mut controller,
+            velocity: &mut velocity,
+            animation: &mut animation,
+            day_cycle: &day_cycle,
+            tunables: &tunables,
+            direction: slot.direction,
+            dt,
+        };
+        if let Some(elapsed) = slot.active {
+            let elapsed = elapsed + dt;
+            if (ability.tick)(&mut ctx, elapsed) {
+                slot.active = Some(elapsed);
+            } else {
+                (ability.end)(&mut ctx);
+                slot.active = None;
+                changed = true;
+                ended.send(AbilityEnded {ability: slot.ability});
+            }
+            continue;
+        }
+        if !input.abilities.contains(&slot.action) {continue}
+        let cost = (ability.cost)(&tunables);
+        if slot.cooldown > 0. || ctx.health.current <= cost {
+            play_sound.send(PlaySoundEvent::DashCD);
+            continue;
+        }
+        ctx.direction = input.movement;
+        if !(ability.activate)(&mut ctx) {continue}
+        ctx.health.current -= cost;
+        slot.direction = input.movement;
+        slot.cooldown = (ability.cooldown)(player, &tunables);
+        slot.active = Some(0.);
+        changed = true;
+        if let Some(sound) = ability.sound {
+            play_sound.send(sound);
+        }
+        used.send(AbilityUsed {ability: slot.ability});
+    }
+    // what an ability ignores may depend on the time of day, e.g. the dash at dusk
+    if changed || (day_cycle.is_changed() && abilities.is_any_active()) {
+        let new_groups = player_collision_groups(abilities.ignored_groups(&registry, &day_cycle));
+        if new_groups != *groups {
+            commands.entity(entity).insert(new_groups);
+        }
+    }
+}
+
+/// A bat may come down over a fence or a grave, the vampire lands on the nearest free spot instead.
+pub fn land_bat(
+    mut ended: EventReader<AbilityEnded>,
+    mut player: Query<(Entity, &mut Transform, &Collider), With<Player>>,
+    rapier_context: Res<RapierContext>,
+) {
+    if !ended.read().any(|e| e.ability == AbilityId::BatForm) {return}
+    let Ok((entity, mut transform, collider)) = player.get_single_mut() else {return};
+    let filter = QueryFilter::new()
+        .exclude_rigid_body(entity)
+        .exclude_sensors()
+        .groups(CollisionGroups::new(
+            Group::from_bits(PLAYER_CG).unwrap(),
+            Group::from_bits(STRUCTURES_CG | RAYCASTABLE_STRUCT_CG).unwrap(),
+        ));
+    let blocked = |pos: Vec2| rapier_context.intersection_with_shape(pos, 0., collider, filter).is_some();
+    let pos = transform.translation.xy();
+    if !blocked(pos) {return}
+    let free = (1..=LAND_RINGS).flat_map(|ring| {
+        let points = ring * 8;
+        (0..points).map(move |i| {
+            let angle = i as f32 / points as f32 * std::f32::consts::TAU;
+            pos + Vec2::from_angle(angle) * ring as f32 * LAND_STEP
+        })
+    }).find(|p| !blocked(*p));
+    // nowhere to go, the physics pushes it out
+    let Some(free) = free else {return};
+    transform.translation = free.extend(transform.translation.z);
+}
+
+/// Bats burst out of the player when it turns into one and back.
+pub fn bat_form_particles(
+    mut commands: Commands,
+    mut used: EventReader<AbilityUsed>,
+    mut ended: EventReader<AbilityEnded>,
+    player: Query<&Transform, With<Player>>,
+    mut layout_handles: ResMut<TextureAtlasLayoutHandles>,
+    asset_server: Res<AssetServer>,
+    mut rng: ResMut<GameRng>,
+) {
+    let transformed = used.read().filter(|u| u.ability == AbilityId::BatForm).count()
+        + ended.read().filter(|e| e.ability == AbilityId::BatForm).count();
+    let Ok(transform) = player.get_single() else {return};
+    for _ in 0..transformed * 6 {
+        spawn_bat_particle(&mut commands, &mut layout_handles, &asset_server, &mut rng, transform.translation);
+    }
+}
+
+/// At night the dash goes through npcs (to bite them) as a sensor, until it hits a wall.
+fn dash_activate(ctx: &mut AbilityContext) -> bool {
+    if ctx.day_cycle.is_night {
+        ctx.commands.entity(ctx.entity).insert(Sensor);
+    }
+    true
+}
+
+fn dash_tick(ctx: &mut AbilityContext, elapsed: f32) -> bool {
+    let t = elapsed * ctx.player.dash_tick;
+    let new_max = ctx.player.max_speed * ctx.player.dash.sample(t);
+    let new_gain = ctx.player.accumulation_gain * ctx.player.dash.sample(t);
+    let controller = &mut ctx.controller;
+    controller.accumulated_velocity = controller.accumulated_velocity.move_towards(ctx.direction.normalize_or_zero() * new_max, ctx.dt * new_gain);
+    if controller.accumulated_velocity.length() > new_max {controller.accumulated_velocity = controller.accumulated_velocity.normalize() * new_max}
+    ctx.velocity.linvel = controller.accumulated_velocity;
+    t < DASH_DURATION
+}
+
+fn dash_end(ctx: &mut AbilityContext) {
+    ctx.commands.entity(ctx.entity).remove::<Sensor>();
+}
+
+fn bat_activate(ctx: &mut AbilityContext) -> bool {
+    ctx.commands.entity(ctx.entity).insert(BatForm);
+    ctx.animation.become_bat();
+    true
+}
+
+/// Drains health on top of the hunger, ends early when there is none left to drain.
+fn bat_tick(ctx: &mut AbilityContext, elapsed: f32) -> bool {
+    ctx.health.current -= ctx.tunables.bat_drain * ctx.dt;
+    elapsed < ctx.tunables.bat_duration && ctx.health.current > ctx.tunables.bat_drain
+}
+
+fn bat_end(ctx: &mut AbilityContext) {
+    ctx.commands.entity(ctx.entity).remove::<BatForm>();
+    ctx.animation.become_vampire();
+}
@@ -0,0 +1,143 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::core::damage::{Damageable, Health, Resistances};
+
+use super::components::Player;
+
+pub const ARCHETYPES_PATH: &str = "player/vampires.archetypes.ron";
+
+/// Every vampire the player can pick on the start screen, loaded from [`ARCHETYPES_PATH`].
+#[derive(Asset, TypePath, Resource, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Archetypes {
+    pub archetypes: Vec<Archetype>,
+}
+
+impl Default for Archetypes {
+    fn default() -> Self {
+        Archetypes {archetypes: vec![Archetype::default()]}
+    }
+}
+
+impl Archetypes {
+    /// Falls back to the built in one, so an empty file can't leave the player without stats.
+    pub fn get(&self, index: usize) -> Archetype {
+        self.archetypes.get(index).cloned().unwrap_or_default()
+    }
+
+    pub fn count(&self) -> usize {
+        self.archetypes.len().max(1)
+    }
+}
+
+/// Index into [`Archetypes`], kept over restarts.
+#[derive(Resource, Default, Clone, Copy, Debug)]
+pub struct SelectedArchetype {
+    pub index: usize,
+}
+
+/// Base stats of a fresh run and the look of the player.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Archetype {
+    pub name: String,
+    pub description: String,
+    /// Same layout as `player/vampire.png`.
+    pub sprite: String,
+    pub tint: [f32; 3],
+    pub max_hp: f32,
+    pub max_speed: f32,
+    pub accumulation_gain: f32,
+    pub resistances: Resistances,
+    pub dash_cd: f32,
+    pub dash_tick: f32,
+    pub hp_gain: f32,
+    pub xp_gain: f32,
+    pub hunger_rate: f32,
+    pub max_xp: f32,
+    pub dash: DashCurve,
+}
+
+impl Default for Archetype {
+    fn default() -> Self {
+        Archetype {
+            name: "Fledgling".to_string(),
+            description: "Young and hungry.".to_string(),
+            sprite: "player/vampire.png".to_string(),
+            tint: [1., 1., 1.],
+            max_hp: 80.,
+            max_speed: 60.,
+            accumulation_gain: 500.,
+            resistances: Resistances::default(),
+            dash_cd: 1.5,
+            dash_tick: 1.,
+            hp_gain: 5.,
+            xp_gain: 10.,
+            hunger_rate: 2.,
+            max_xp: 100.,
+            dash: DashCurve::default(),
+        }
+    }
+}
+
+impl Archetype {
+    pub fn player(&self) -> Player {
+        Player {
+            xp: 0.,
+            score: 0.,
+            max_speed: self.max_speed,
+            accumulation_gain: self.accumulation_gain,
+            dash_cd: self.dash_cd,
+            dash_tick: self.dash_tick,
+            hp_gain: self.hp_gain,
+            xp_gain: self.xp_gain,
+            hunger_rate: self.hunger_rate,
+            max_xp: self.max_xp,
+            dash: self.dash,
+            is_dead: false,
+        }
+    }
+
+    pub fn health(&self) -> Health {
+        Health::new(self.max_hp)
+    }
+
+    pub fn damageable(&self, invulnerability: f32) -> Damageable {
+        Damageable {resistances: self.resistances, invulnerability}
+    }
+
+    pub fn tint(&self) -> Color {
+        Color::srgb(self.tint[0], self.tint[1], self.tint[2])
+    }
+}
+
+/// Speed multiplier over a dash: a gaussian bump of height `peak` around `center`,
+/// sampled at `start - rate * t`.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DashCurve {
+    pub peak: f32,
+    pub start: f32,
+    pub rate: f32,
+    pub center: f32,
+    pub width: f32,
+}
+
+impl Default for DashCurve {
+    fn default() -> Self {
+        DashCurve {
+            peak: 5.,
+            start: 3.,
+            rate: 5.,
+            center: 1.639964,
+            width: 0.800886,
+        }
+    }
+}
+
+impl DashCurve {
+    pub fn sample(&self, t: f32) -> f32 {
+        let x = self.start - self.rate * t;
+        self.peak * std::f32::consts::E.powf(-(x - self.center).powf(2.) / (2. * self.width.powf(2.)))
+    }
+}
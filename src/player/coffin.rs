@@ -0,0 +1,179 @@
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::{RapierConfiguration, Sensor, TimestepMode, Velocity};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    characters::animation::AnimationController,
+    core::{damage::{Health, Invulnerable}, default::plugin::rapier_configuration, tunables::Tunables},
+    map::plugin::Coffin,
+    npc::components::{Held, NpcState},
+    sounds::components::PlaySoundEvent,
+    systems::DayCycle,
+};
+
+use super::{abilities::{player_collision_groups, Abilities, BatForm}, components::{Player, PlayerInput}, feeding::Feeding, systems::{PlayerController, BULLET_CG, NPC_CG, RAYCASTABLE_STRUCT_CG, STRUCTURES_CG}};
+
+/// Real seconds of the fade to black, and back.
+const FADE_DURATION: f32 = 0.6;
+
+/// The last coffin the player rested in, where it comes back after dying.
+#[derive(Resource, Default, Clone, Debug, Serialize, Deserialize)]
+pub struct CoffinCheckpoint {
+    pub pos: Option<Vec2>,
+}
+
+/// On the player while it sleeps in a coffin, or wakes up in one after dying.
+#[derive(Component)]
+pub struct Slumber {
+    /// 0 clear, 1 black.
+    fade: f32,
+    waking: bool,
+}
+
+/// Full screen black, faded in and out around a sleep.
+#[derive(Component)]
+pub struct SleepFade;
+
+/// Sent instead of [`KillPlayer`](super::components::KillPlayer) when there is a checkpoint to come back to.
+#[derive(Event)]
+pub struct ReviveAtCoffin;
+
+/// Resting in a coffin sets it as the checkpoint, by day the player also sleeps in it until dusk.
+pub fn use_coffin(
+    mut commands: Commands,
+    input: Res<PlayerInput>,
+    mut player: Query<(Entity, &Player, &mut Transform, &mut Velocity, &Abilities, Has<Slumber>, Has<Feeding>)>,
+    coffins: Query<&GlobalTransform, With<Coffin>>,
+    mut checkpoint: ResMut<CoffinCheckpoint>,
+    day_cycle: Res<DayCycle>,
+    tunables: Res<Tunables>,
+    mut play_sound: EventWriter<PlaySoundEvent>,
+) {
+    if !input.confirm {return}
+    let Ok((entity, player, mut transform, mut velocity, abilities, is_sleeping, is_feeding)) = player.get_single_mut() else {return};
+    if player.is_dead || is_sleeping || is_feeding || abilities.is_any_active() {return}
+    let pos = transform.translation.xy();
+    let Some(coffin) = coffins.iter()
+        .map(|t| t.translation().xy())
+        .filter(|c| c.distance(pos) < tunables.coffin_range)
+        .min_by(|a, b| a.distance(pos).total_cmp(&b.distance(pos))) else {return};
+    checkpoint.pos = Some(coffin);
+    play_sound.send(PlaySoundEvent::Selected);
+    if day_cycle.is_night {return}
+    transform.translation = coffin.extend(transform.translation.z);
+    velocity.linvel = Vec2::ZERO;
+    fall_asleep(&mut commands, entity, 0., false);
+}
+
+/// Fades out, runs the world faster until dusk, then fades back in.
+/// Nothing touches the player in the meantime.
+pub fn update_slumber(
+    mut commands: Commands,
+    mut player: Query<(Entity, &mut Slumber, &mut Velocity)>,
+    mut fades: Query<(Entity, &mut BackgroundColor), With<SleepFade>>,
+    mut virtual_time: ResMut<Time<Virtual>>,
+    mut rapier_config: ResMut<RapierConfiguration>,
+    real_time: Res<Time<Real>>,
+    day_cycle: Res<DayCycle>,
+    tunables: Res<Tunables>,
+) {
+    let Ok((entity, mut slumber, mut velocity)) = player.get_single_mut() else {return};
+    velocity.linvel = Vec2::ZERO;
+    let step = real_time.delta_seconds() / FADE_DURATION;
+    if slumber.waking {
+        slumber.fade = (slumber.fade - step).max(0.);
+    } else {
+        slumber.fade = (slumber.fade + step).min(1.);
+        if slumber.fade >= 1. {
+            set_world_speed(&mut virtual_time, &mut rapier_config, tunables.sleep_speed);
+        }
+        if day_cycle.is_night {
+            set_world_speed(&mut virtual_time, &mut rapier_config, 1.);
+            slumber.waking = true;
+        }
+    }
+    for (fade, mut color) in fades.iter_mut() {
+        color.0 = Color::srgba(0., 0., 0., slumber.fade);
+        if slumber.waking && slumber.fade <= 0. {
+            commands.entity(fade).despawn_recursive();
+        }
+    }
+    if slumber.waking && slumber.fade <= 0. {
+        commands.entity(entity).remove::<Slumber>().insert((
+            Visibility::Visible,
+            player_collision_groups(0),
+        ));
+    }
+}
+
+/// Dying with a checkpoint costs a share of the xp and score instead of the run.
+pub fn revive_at_coffin(
+    mut commands: Commands,
+    mut revive: EventReader<ReviveAtCoffin>,
+    mut player: Query<(Entity, &mut Player, &mut Health, &mut Transform, &mut Velocity, &mut PlayerController, &mut Abilities, &mut AnimationController, Option<&Feeding>)>,
+    mut victims: Query<&mut NpcState, With<Held>>,
+    checkpoint: Res<CoffinCheckpoint>,
+    tunables: Res<Tunables>,
+    mut play_sound: EventWriter<PlaySoundEvent>,
+) {
+    if revive.read().count() == 0 {return}
+    let Some(pos) = checkpoint.pos else {return};
+    let Ok((entity, mut player, mut health, mut transform, mut velocity, mut controller, mut abilities, mut animation, feeding)) = player.get_single_mut() else {return};
+    health.current = health.max;
+    player.xp *= 1. - tunables.coffin_xp_cost;
+    player.score *= 1. - tunables.coffin_score_cost;
+    transform.translation = pos.extend(transform.translation.z);
+    velocity.linvel = Vec2::ZERO;
+    *controller = PlayerController::default();
+    abilities.reset();
+    animation.become_vampire();
+    if let Some(feeding) = feeding {
+        feeding.release(&mut commands, entity, victims.get_mut(feeding.victim).ok().as_deref_mut());
+    }
+    // an ability cut short by the death never ran its end
+    commands.entity(entity).remove::<(BatForm, Sensor)>().insert(Invulnerable::new(tunables.invulnerability));
+    play_sound.send(PlaySoundEvent::LvlUp);
+    fall_asleep(&mut commands, entity, 1., true);
+}
+
+/// Resets the world speed in case the run ended mid sleep.
+pub fn reset_sleep_speed(mut virtual_time: ResMut<Time<Virtual>>, mut rapier_config: ResMut<RapierConfiguration>) {
+    set_world_speed(&mut virtual_time, &mut rapier_config, 1.);
+}
+
+/// The physics takes one normal sized step per `speed`, so nothing tunnels through walls
+/// and it keeps up with the faster clock.
+fn set_world_speed(virtual_time: &mut Time<Virtual>, rapier_config: &mut RapierConfiguration, speed: f32) {
+    virtual_time.set_relative_speed(speed);
+    rapier_config.timestep_mode = match rapier_configuration().timestep_mode {
+        TimestepMode::Variable {max_dt, time_scale, ..} => TimestepMode::Variable {
+            max_dt: max_dt * speed,
+            time_scale,
+            substeps: speed.ceil().max(1.) as usize,
+        },
+        mode => mode,
+    };
+}
+
+/// Hides the player in the coffin, out of reach of everything, behind a black screen.
+fn fall_asleep(commands: &mut Commands, player: Entity, fade: f32, waking: bool) {
+    commands.entity(player).insert((
+        Slumber {fade, waking},
+        Visibility::Hidden,
+        player_collision_groups(BULLET_CG | NPC_CG | STRUCTURES_CG | RAYCASTABLE_STRUCT_CG),
+    ));
+    commands.spawn((
+        NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                width: Val::Percent(100.),
+                height: Val::Percent(100.),
+                ..default()
+            },
+            background_color: BackgroundColor(Color::srgba(0., 0., 0., fade)),
+            z_index: ZIndex::Global(50),
+            ..default()
+        },
+        SleepFade,
+    ));
+}
@@ -1,63 +1,48 @@
 use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
 
-#[derive(Component)]
+use crate::core::{input::Action, settings::SettingEntry};
+
+use super::archetype::{Archetype, DashCurve};
+
+#[derive(Component, Clone, Debug, Serialize, Deserialize)]
+/// Health and resistances live in the shared [`Health`](crate::core::damage::Health)
+/// and [`Damageable`](crate::core::damage::Damageable) components.
 pub struct Player {
-    pub hp: f32,
     pub xp: f32,
     pub score: f32,
     pub max_speed: f32,
     pub accumulation_gain: f32,
     pub dash_cd: f32,
     pub dash_tick: f32,
-    pub phys_res: f32,
     pub hp_gain: f32,
     pub xp_gain: f32,
     pub hunger_rate: f32,
     pub max_xp: f32,
-    pub max_hp: f32,
+    pub dash: DashCurve,
     pub is_dead: bool,
 } 
 
 impl Default for Player {
     fn default() -> Self {
-        Player {
-            hp: 80.,
-            xp: 0., 
-            score: 0., 
-            max_speed: 60., 
-            accumulation_gain: 500., 
-            phys_res: 0.2, 
-            dash_cd: 1.5,
-            dash_tick: 1.,
-            hp_gain: 5., 
-            xp_gain: 10.,
-            hunger_rate: 2.,
-            max_xp: 100., 
-            max_hp: 80., 
-            is_dead: false
-        }
+        Archetype::default().player()
     }
 }
 
 
 // armor (phys res); speed; hp gain; xp gain; max hp;
 
-#[derive(Component)]
-pub struct DashTimer {
-    pub timer: Timer,
-}
-
-#[derive(Event)]
-pub struct HitPlayer {
-    pub dmg_type: u8,
-}
-
 #[derive(Event)]
 pub struct KillNpc {
+    pub npc: Entity,
     pub npc_type: u8,
+    /// The hp and xp already flowed in while feeding, only the score is left.
+    pub drained: bool,
+    /// Where the npc died, for witnesses.
+    pub pos: Vec2,
 }
 
-#[derive(Component)]
+#[derive(Component, Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
 pub enum UpgradeButton {
     MaxHp,
     Armor,
@@ -66,7 +51,45 @@ pub enum UpgradeButton {
     Speed,
     HungerRate,
     DashCD,
-    DashTick
+    DashTick,
+    BatForm,
+}
+
+/// Everything the player did this frame.
+/// Filled from the bound actions and ui, or from a replay when one is playing.
+#[derive(Resource, Default, Clone, Debug, Serialize, Deserialize)]
+pub struct PlayerInput {
+    pub movement: Vec2,
+    /// Just pressed ability slot actions.
+    pub abilities: Vec<Action>,
+    pub bite: bool,
+    pub pause: bool,
+    /// -1 up, 1 down
+    pub menu_nav: i32,
+    /// -1 left, 1 right
+    pub menu_adjust: i32,
+    pub confirm: bool,
+    pub menu: Option<MenuChoice>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum MenuChoice {
+    Start,
+    /// Picks up the saved run from the start screen.
+    Continue,
+    Resume,
+    Restart,
+    /// Back to the start screen from the run summary.
+    MainMenu,
+    Settings,
+    Quit,
+    Back,
+    Setting(SettingEntry),
+    Upgrade(UpgradeButton),
+    /// Draws other upgrades on the level up screen.
+    Reroll,
+    /// Steps to the next archetype on the start screen.
+    Archetype,
 }
 
 #[derive(Component)]
@@ -79,7 +102,7 @@ pub struct KillPlayer {
     pub won: bool,
 }
 
-#[derive(Resource)]
+#[derive(Resource, Clone)]
 pub struct DeathTimer {
     pub timer: Timer,
 }
@@ -0,0 +1,190 @@
+use bevy::prelude::*;
+use bevy::math::{vec2, vec3};
+use bevy_rapier2d::prelude::Velocity;
+
+use crate::{
+    characters::animation::AnimationController,
+    core::{damage::{DamageKind, DamageTaken, Health}, functions::TextureAtlasLayoutHandles, rng::GameRng, tunables::Tunables},
+    npc::components::{Civilian, Held, NpcState},
+    sounds::components::PlaySoundEvent,
+    stuff::{spawn_follow_blood_particle, spawn_warn_particle},
+    systems::DayCycle,
+};
+
+use super::{abilities::BatForm, components::{KillNpc, Player, PlayerInput}, stats::RunStats};
+
+/// Seconds between the victim's frightened particles.
+const FEAR_PARTICLE_CD: f32 = 0.6;
+const BAR_WIDTH: f32 = 16.;
+
+/// On the player while it drinks from a held civilian.
+#[derive(Component)]
+pub struct Feeding {
+    pub victim: Entity,
+    /// Seconds since the bite, the feed is done at `feed_duration`.
+    pub elapsed: f32,
+    particle_cd: f32,
+    /// Root of the bar over the player's head.
+    bar: Entity,
+}
+
+impl Feeding {
+    pub fn progress(&self, tunables: &Tunables) -> f32 {
+        (self.elapsed / tunables.feed_duration).clamp(0., 1.)
+    }
+
+    /// Lets go of the victim, which runs unless it is dead, and takes the bar down.
+    pub fn release(&self, commands: &mut Commands, player: Entity, victim_state: Option<&mut NpcState>) {
+        if let Some(state) = victim_state {
+            if *state != NpcState::Dead {
+                *state = NpcState::Escape;
+            }
+        }
+        commands.entity(self.victim).remove::<Held>();
+        commands.entity(self.bar).despawn_recursive();
+        commands.entity(player).remove::<Feeding>();
+    }
+}
+
+#[derive(Component)]
+pub struct FeedingBar;
+
+#[derive(Component)]
+pub struct FeedingBarFill;
+
+/// Grabs the closest civilian in reach, only at night and not as a bat.
+pub fn start_feeding(
+    mut commands: Commands,
+    input: Res<PlayerInput>,
+    day_cycle: Res<DayCycle>,
+    tunables: Res<Tunables>,
+    mut player: Query<(Entity, &Player, &Transform, &mut AnimationController, Has<Feeding>, Has<BatForm>)>,
+    mut civilians: Query<(Entity, &Transform, &NpcState, &mut Velocity), (With<Civilian>, Without<Held>, Without<Player>)>,
+    mut play_sound: EventWriter<PlaySoundEvent>,
+) {
+    if !input.bite || !day_cycle.is_night {return}
+    let Ok((entity, player, transform, mut animation, is_feeding, is_bat)) = player.get_single_mut() else {return};
+    if player.is_dead || is_feeding || is_bat {return}
+    let pos = transform.translation.xy();
+    let closest = civilians.iter_mut()
+        .filter(|(_, t, state, _)| **state != NpcState::Dead && t.translation.xy().distance(pos) < tunables.feed_range)
+        .min_by(|a, b| a.1.translation.xy().distance(pos).total_cmp(&b.1.translation.xy().distance(pos)));
+    let Some((victim, victim_transform, _, mut velocity)) = closest else {return};
+    velocity.linvel = Vec2::ZERO;
+    let dir = victim_transform.translation.xy() - pos;
+    if dir.x.abs() > dir.y.abs() {
+        if dir.x > 0. {animation.turn_right()} else {animation.turn_left()}
+    } else if dir.y > 0. {
+        animation.turn_up()
+    } else {
+        animation.turn_down()
+    }
+    animation.play_feed();
+    let bar = spawn_feeding_bar(&mut commands, entity);
+    commands.entity(victim).insert(Held);
+    commands.entity(entity).insert(Feeding {victim, elapsed: 0., particle_cd: 0., bar});
+    play_sound.send(PlaySoundEvent::Hit);
+}
+
+/// Drains the victim into the player over `feed_duration`.
+/// A hunter's hit, daylight, drifting apart or biting again lets the victim go, bleeding.
+pub fn update_feeding(
+    mut commands: Commands,
+    mut player: Query<(Entity, &mut Player, &mut Health, &Transform, &mut Velocity, &mut Feeding), Without<Civilian>>,
+    mut victims: Query<(&Transform, &mut Health, &mut NpcState, &mut AnimationController, &mut Velocity), (With<Civilian>, Without<Player>)>,
+    mut bars: Query<(&Parent, &mut Transform), (With<FeedingBarFill>, Without<Player>, Without<Civilian>)>,
+    mut taken: EventReader<DamageTaken>,
+    mut kill_npc: EventWriter<KillNpc>,
+    mut play_sound: EventWriter<PlaySoundEvent>,
+    mut stats: ResMut<RunStats>,
+    mut layout_handles: ResMut<TextureAtlasLayoutHandles>,
+    asset_server: Res<AssetServer>,
+    mut rng: ResMut<GameRng>,
+    input: Res<PlayerInput>,
+    day_cycle: Res<DayCycle>,
+    tunables: Res<Tunables>,
+    time: Res<Time>,
+) {
+    // drained every frame, or a hit from before the bite would break it off
+    let hunter_hits: Vec<Entity> = taken.read()
+        .filter(|hit| matches!(hit.kind, DamageKind::Projectile | DamageKind::Holy))
+        .map(|hit| hit.target)
+        .collect();
+    let Ok((entity, mut player, mut health, transform, mut velocity, mut feeding)) = player.get_single_mut() else {return};
+    let hunter_hit = hunter_hits.contains(&entity);
+    let Ok((victim_transform, mut victim_health, mut victim_state, mut victim_animation, mut victim_velocity)) = victims.get_mut(feeding.victim) else {
+        // the victim is gone, nothing to let go of
+        commands.entity(feeding.bar).despawn_recursive();
+        commands.entity(entity).remove::<Feeding>();
+        return;
+    };
+    let victim_pos = victim_transform.translation;
+    let released = input.bite && feeding.elapsed > 0.;
+    let too_far = victim_pos.xy().distance(transform.translation.xy()) > tunables.feed_range * 1.5;
+    if player.is_dead || hunter_hit || released || too_far || !day_cycle.is_night || *victim_state == NpcState::Dead {
+        let length = ((1. - victim_health.fraction()) * 5.).ceil() as usize;
+        spawn_follow_blood_particle(&mut commands, &mut layout_handles, &asset_server, feeding.victim, victim_pos, length);
+        feeding.release(&mut commands, entity, Some(&mut *victim_state));
+        return;
+    }
+
+    let dt = time.delta_seconds();
+    let step = (dt / tunables.feed_duration).min(1. - feeding.progress(&tunables));
+    feeding.elapsed += dt;
+    velocity.linvel = Vec2::ZERO;
+    victim_velocity.linvel = Vec2::ZERO;
+    victim_animation.play_hurt();
+
+    health.heal(player.hp_gain * step);
+    let xp = player.xp_gain * step;
+    player.xp += xp;
+    stats.xp_earned += xp;
+    victim_health.current = (victim_health.current - victim_health.max * step).max(0.);
+
+    feeding.particle_cd -= dt;
+    if feeding.particle_cd <= 0. {
+        feeding.particle_cd = FEAR_PARTICLE_CD;
+        spawn_warn_particle(&mut commands, &mut layout_handles, &asset_server, &mut rng, victim_pos);
+    }
+    let progress = feeding.progress(&tunables);
+    for (parent, mut fill) in bars.iter_mut() {
+        if parent.get() != feeding.bar {continue}
+        // grows from the left edge
+        fill.scale.x = progress;
+        fill.translation.x = -BAR_WIDTH * 0.5 * (1. - progress);
+    }
+
+    if feeding.elapsed >= tunables.feed_duration {
+        victim_health.current = 0.;
+        *victim_state = NpcState::Dead;
+        kill_npc.send(KillNpc {npc: feeding.victim, npc_type: 0, drained: true, pos: victim_pos.xy()});
+        play_sound.send(PlaySoundEvent::Kill);
+        commands.entity(feeding.victim).remove::<Held>();
+        commands.entity(feeding.bar).despawn_recursive();
+        commands.entity(entity).remove::<Feeding>();
+    }
+}
+
+/// A thin bar over the player's head, filled by `update_feeding`.
+fn spawn_feeding_bar(commands: &mut Commands, player: Entity) -> Entity {
+    let bar = commands.spawn((
+        Name::new("FeedingBar"),
+        FeedingBar,
+        SpriteBundle {
+            sprite: Sprite {color: Color::srgb_u8(40, 20, 20), custom_size: Some(vec2(BAR_WIDTH + 2., 4.)), ..default()},
+            transform: Transform::from_translation(vec3(0., 20., 9.)),
+            ..default()
+        },
+    )).with_children(|commands| {
+        commands.spawn((
+            SpriteBundle {
+                sprite: Sprite {color: Color::srgb_u8(169, 96, 45), custom_size: Some(vec2(BAR_WIDTH, 2.)), ..default()},
+                transform: Transform::from_translation(vec3(-BAR_WIDTH * 0.5, 0., 0.1)).with_scale(vec3(0., 1., 1.)),
+                ..default()
+            },
+            FeedingBarFill,
+        ));
+    }).id();
+    commands.entity(player).add_child(bar);
+    bar
+}
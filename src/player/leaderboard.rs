@@ -0,0 +1,192 @@
+use std::{io::Write, time::{SystemTime, UNIX_EPOCH}};
+
+use bevy::prelude::*;
+use bevy::app::Plugin;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    core::{functions::data_path, replay::is_replaying, rng::GameRng, run::RunScopedApp, tunables::Tunables},
+    npc::systems::RosesCollected,
+    systems::{GameState, RunClock},
+    NUM_ROSES,
+};
+
+use super::{archetype::{Archetypes, SelectedArchetype}, components::{Player, UpgradeButton}, stats::RunStats, upgrade_ui::{spawn_game_over_screen, spawn_victory_screen}, upgrades::TakenUpgrades};
+
+/// Runs kept on the leaderboard.
+pub const LEADERBOARD_SIZE: usize = 10;
+
+/// Records every finished run into the leaderboard and the run history.
+/// Only added to the windowed game, headless simulations don't touch the files.
+pub struct LeaderboardPlugin;
+
+impl Plugin for LeaderboardPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Leaderboard::load())
+            // a replayed run is already on the board
+            .add_systems(OnEnter(GameState::GameOver), record_run.run_if(not(is_replaying)).before(spawn_game_over_screen))
+            .add_systems(OnEnter(GameState::Victory), record_run.run_if(not(is_replaying)).before(spawn_victory_screen))
+            // the next run's screens would show this one's rank, a replayed run doesn't get its own
+            .on_run_restart(clear_ranking);
+    }
+}
+
+/// One finished run, as stored on the leaderboard and in the history.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct RunRecord {
+    pub score: f32,
+    pub nights: u32,
+    pub civilians: u32,
+    pub hunters: u32,
+    pub roses: u32,
+    pub won: bool,
+    pub seed: u64,
+    /// Seconds since the unix epoch.
+    pub timestamp: u64,
+    pub archetype: String,
+    pub upgrades: Vec<UpgradeButton>,
+    /// Game version, to tell balance changes apart in the history.
+    pub version: String,
+}
+
+impl RunRecord {
+    pub fn date(&self) -> String {
+        format_date(self.timestamp)
+    }
+}
+
+/// Best runs first.
+#[derive(Resource, Default, Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Leaderboard {
+    pub runs: Vec<RunRecord>,
+}
+
+impl Leaderboard {
+    /// No data dir (web) or a broken file both start an empty board.
+    pub fn load() -> Self {
+        let Some(path) = data_path("leaderboard.ron") else {return Leaderboard::default()};
+        let Ok(data) = std::fs::read_to_string(&path) else {return Leaderboard::default()};
+        match ron::from_str(&data) {
+            Ok(board) => board,
+            Err(e) => {
+                warn!("Invalid leaderboard file {}: {}", path.display(), e);
+                Leaderboard::default()
+            }
+        }
+    }
+
+    pub fn save(&self) {
+        let Some(path) = data_path("leaderboard.ron") else {return};
+        let data = match ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default()) {
+            Ok(data) => data,
+            Err(e) => {error!("Failed to serialize leaderboard: {}", e); return},
+        };
+        if let Some(dir) = path.parent() {
+            let _ = std::fs::create_dir_all(dir);
+        }
+        if let Err(e) = std::fs::write(&path, data) {
+            error!("Failed to write leaderboard {}: {}", path.display(), e);
+        }
+    }
+
+    /// Returns the 0 based rank of the run, `None` if it didn't make the board.
+    pub fn insert(&mut self, record: RunRecord) -> Option<usize> {
+        let rank = self.runs.iter().position(|r| r.score < record.score).unwrap_or(self.runs.len());
+        if rank >= LEADERBOARD_SIZE {return None}
+        self.runs.insert(rank, record);
+        self.runs.truncate(LEADERBOARD_SIZE);
+        Some(rank)
+    }
+}
+
+/// Where the run that just ended landed, read by the death and victory screens.
+#[derive(Resource, Clone, Debug)]
+pub struct RunRanking {
+    pub record: RunRecord,
+    pub rank: Option<usize>,
+}
+
+impl RunRanking {
+    pub fn is_best(&self) -> bool {
+        self.rank == Some(0)
+    }
+}
+
+fn clear_ranking(mut commands: Commands) {
+    commands.remove_resource::<RunRanking>();
+}
+
+/// Every finished run on its own line, never trimmed.
+fn append_history(record: &RunRecord) {
+    let Some(path) = data_path("history.ron") else {return};
+    let line = match ron::ser::to_string(record) {
+        Ok(line) => line,
+        Err(e) => {error!("Failed to serialize run: {}", e); return},
+    };
+    if let Some(dir) = path.parent() {
+        let _ = std::fs::create_dir_all(dir);
+    }
+    let file = std::fs::OpenOptions::new().create(true).append(true).open(&path);
+    if let Err(e) = file.and_then(|mut file| writeln!(file, "{}", line)) {
+        error!("Failed to write run history {}: {}", path.display(), e);
+    }
+}
+
+/// The web build has no system clock, its runs aren't stored anyway.
+fn now() -> u64 {
+    if cfg!(target_arch = "wasm32") {return 0}
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0)
+}
+
+/// `YYYY-MM-DD` in UTC.
+fn format_date(timestamp: u64) -> String {
+    // days to civil date, see http://howardhinnant.github.io/date_algorithms.html
+    let z = (timestamp / 86400) as i64 + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 {mp + 3} else {mp - 9};
+    let year = yoe + era * 400 + if month <= 2 {1} else {0};
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+fn record_run(
+    mut commands: Commands,
+    mut leaderboard: ResMut<Leaderboard>,
+    player: Query<&Player>,
+    state: Res<State<GameState>>,
+    stats: Res<RunStats>,
+    roses: Res<RosesCollected>,
+    clock: Res<RunClock>,
+    taken: Res<TakenUpgrades>,
+    archetypes: Res<Archetypes>,
+    selected: Res<SelectedArchetype>,
+    rng: Res<GameRng>,
+    tunables: Res<Tunables>,
+) {
+    let Ok(player) = player.get_single() else {return};
+    let won = *state.get() == GameState::Victory;
+    let record = RunRecord {
+        score: player.score,
+        nights: tunables.nights_survived(clock.elapsed),
+        civilians: stats.civilians_killed,
+        hunters: stats.hunters_killed,
+        roses: if won {NUM_ROSES} else {roses.collected},
+        won,
+        seed: rng.seed(),
+        timestamp: now(),
+        archetype: archetypes.get(selected.index).name,
+        upgrades: taken.taken.clone(),
+        version: env!("CARGO_PKG_VERSION").to_string(),
+    };
+    append_history(&record);
+    let rank = leaderboard.insert(record.clone());
+    if rank.is_some() {
+        leaderboard.save();
+    }
+    commands.insert_resource(RunRanking {record, rank});
+}
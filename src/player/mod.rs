@@ -1,25 +1,72 @@
 use bevy::prelude::*;
-use components::{DeathTimer, HitPlayer, KillNpc, KillPlayer};
+use components::{DeathText, DeathTimer, KillNpc, KillPlayer, PlayerInput};
 use systems::*;
-use upgrade_ui::interact_upgrade_button;
+use upgrade_ui::{apply_upgrade, draw_upgrades, interact_upgrade_button, reroll_upgrades, spawn_game_over_screen, spawn_lvl_up_screen, spawn_victory_screen, LvlUpScreen};
+use upgrades::{TakenUpgrades, UpgradeOffer, UpgradeRegistry};
+use archetype::{Archetypes, SelectedArchetype, ARCHETYPES_PATH};
+use stats::{track_run_stats, RunStats};
+use sun::{sun_exposure, SunExposure};
+use visibility::{update_player_visibility, PlayerVisibility};
+use feeding::{start_feeding, update_feeding, FeedingBar};
+use coffin::{reset_sleep_speed, revive_at_coffin, update_slumber, use_coffin, CoffinCheckpoint, ReviveAtCoffin, SleepFade};
+use abilities::{bat_form_particles, land_bat, update_abilities, AbilityEnded, AbilityRegistry, AbilityUsed};
 
-use crate::{spawn_score, systems::GameState};
+use crate::{core::{damage::DamageSystem, functions::despawn_with, input::ActionSystem, ron_asset::RonAssetPlugin, run::RunScopedApp, replay::{feed_replay_input, is_replaying}}, npc::systems::manage_civilians, spawn_score, systems::GameState};
 
 pub mod systems;
 pub mod components;
 pub mod upgrade_ui;
+pub mod archetype;
+pub mod upgrades;
+pub mod leaderboard;
+pub mod stats;
+pub mod abilities;
+pub mod feeding;
+pub mod visibility;
+pub mod sun;
+pub mod coffin;
 
 pub struct PlayerPlugin;
 
 impl Plugin for PlayerPlugin {
     fn build(&self, app: &mut App) {
         app
-        .add_event::<HitPlayer>()
         .add_event::<KillNpc>()
         .add_event::<KillPlayer>()
-        .insert_resource(DeathTimer {timer: Timer::from_seconds(5., TimerMode::Repeating)})
+        .add_event::<AbilityUsed>()
+        .add_event::<AbilityEnded>()
+        .add_event::<ReviveAtCoffin>()
+        .run_scoped_resource(DeathTimer {timer: Timer::from_seconds(5., TimerMode::Repeating)})
+        .init_resource::<PlayerInput>()
+        .add_plugins(RonAssetPlugin::<Archetypes>::new(ARCHETYPES_PATH, "archetypes.ron"))
+        .init_resource::<SelectedArchetype>()
+        .init_resource::<UpgradeRegistry>()
+        .init_resource::<AbilityRegistry>()
+        .run_scoped_resource(TakenUpgrades::default())
+        .run_scoped_resource(UpgradeOffer::default())
+        .run_scoped_resource(RunStats::default())
+        .run_scoped_resource(PlayerVisibility::default())
+        .run_scoped_resource(SunExposure::default())
+        .run_scoped_resource(CoffinCheckpoint::default())
+        .on_run_restart(respawn_player)
+        .on_run_restart(despawn_with::<FeedingBar>)
+        .on_run_restart(despawn_with::<SleepFade>)
+        .on_run_restart(reset_sleep_speed)
+        .add_systems(OnExit(GameState::MainMenu), respawn_player)
         .add_systems(Startup, (spawn_player_first_time, spawn_score).chain())
-        .add_systems(Update, ((player_controller, (hit_player, kill_player).chain(), kill_npc, manage_xp).run_if(in_state(GameState::InGame)), interact_upgrade_button))
+        .add_systems(PreUpdate, (read_player_input.run_if(not(is_replaying)), feed_replay_input).chain().after(ActionSystem))
+        .add_systems(Update, (
+            ((player_controller, update_abilities).chain(), (hit_player, revive_at_coffin, kill_player).chain().after(DamageSystem), (use_coffin, update_slumber).chain().before(player_controller), kill_npc, manage_xp, track_run_stats.after(DamageSystem).after(update_abilities), bat_form_particles.after(update_abilities).before(manage_civilians), land_bat.after(update_abilities), update_player_visibility.after(update_abilities), sun_exposure.before(hit_player),
+                (start_feeding, update_feeding).chain().before(player_controller).after(DamageSystem).before(kill_npc).before(manage_civilians)).run_if(in_state(GameState::Playing)),
+            (interact_upgrade_button.run_if(not(is_replaying)), apply_upgrade, reroll_upgrades).chain().run_if(in_state(GameState::LevelUp)),
+            tick_death_screen.run_if(in_state(GameState::GameOver).or_else(in_state(GameState::Victory))),
+        ))
+        .add_systems(OnEnter(GameState::LevelUp), (draw_upgrades, spawn_lvl_up_screen).chain())
+        .add_systems(OnExit(GameState::LevelUp), despawn_with::<LvlUpScreen>)
+        .add_systems(OnEnter(GameState::GameOver), spawn_game_over_screen)
+        .add_systems(OnExit(GameState::GameOver), despawn_with::<DeathText>)
+        .add_systems(OnEnter(GameState::Victory), spawn_victory_screen)
+        .add_systems(OnExit(GameState::Victory), despawn_with::<DeathText>)
         ;
     }
 }
\ No newline at end of file
@@ -0,0 +1,86 @@
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::Velocity;
+use serde::{Deserialize, Serialize};
+
+use crate::{core::damage::{DamageKind, DamageTaken}, systems::DayCycle};
+
+use super::{abilities::{AbilityId, AbilityUsed}, components::{KillNpc, Player}};
+
+/// Everything the summary screen shows about the current run, reset with the run.
+#[derive(Resource, Default, Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RunStats {
+    pub civilians_killed: u32,
+    pub hunters_killed: u32,
+    pub damage_taken: HashMap<DamageKind, f32>,
+    pub dashes: u32,
+    pub distance: f32,
+    pub night_time: f32,
+    pub day_time: f32,
+    pub xp_earned: f32,
+}
+
+impl RunStats {
+    pub fn total_damage_taken(&self) -> f32 {
+        self.damage_taken.values().sum()
+    }
+
+    /// One line per stat, damage split by kind.
+    pub fn lines(&self) -> Vec<String> {
+        let mut lines = vec![
+            format!("Civilians bitten: {}", self.civilians_killed),
+            format!("Hunters bitten: {}", self.hunters_killed),
+            format!("Damage taken: {:.0}", self.total_damage_taken()),
+        ];
+        for kind in DamageKind::ALL {
+            if let Some(amount) = self.damage_taken.get(&kind) {
+                lines.push(format!("  {:?}: {:.0}", kind, amount));
+            }
+        }
+        lines.extend([
+            format!("Dashes: {}", self.dashes),
+            format!("Distance: {:.0}", self.distance),
+            format!("Night: {:.0}s  Day: {:.0}s", self.night_time, self.day_time),
+            format!("XP earned: {:.0}", self.xp_earned),
+        ]);
+        lines
+    }
+}
+
+/// Xp is counted where it is earned, in `kill_npc`.
+pub fn track_run_stats(
+    mut stats: ResMut<RunStats>,
+    mut kill_npc: EventReader<KillNpc>,
+    mut used: EventReader<AbilityUsed>,
+    mut taken: EventReader<DamageTaken>,
+    player: Query<(Entity, &Velocity), With<Player>>,
+    day_cycle: Res<DayCycle>,
+    time: Res<Time>,
+) {
+    for kill in kill_npc.read() {
+        match kill.npc_type {
+            0 => stats.civilians_killed += 1,
+            1 => stats.hunters_killed += 1,
+            _ => {}
+        }
+    }
+    for used in used.read() {
+        if used.ability == AbilityId::Dash {
+            stats.dashes += 1;
+        }
+    }
+    let Ok((entity, velocity)) = player.get_single() else {return};
+    for hit in taken.read() {
+        if hit.target != entity {continue}
+        *stats.damage_taken.entry(hit.kind).or_default() += hit.amount;
+    }
+    let dt = time.delta_seconds();
+    stats.distance += velocity.linvel.length() * dt;
+    if day_cycle.is_night {
+        stats.night_time += dt;
+    } else {
+        stats.day_time += dt;
+    }
+}
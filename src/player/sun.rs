@@ -0,0 +1,38 @@
+use bevy::prelude::*;
+
+use crate::{
+    characters::animation::AnimationController,
+    core::{damage::Health, tunables::Tunables},
+    map::{plugin::ShadeCells, tilemap::TransformToGrid},
+    systems::DayCycle,
+};
+
+use super::{coffin::Slumber, components::Player};
+
+/// How much sun the player is taking this frame, 0 in the shade or at night, 1 in the open at noon.
+#[derive(Resource, Default, Clone, Copy, Debug)]
+pub struct SunExposure(pub f32);
+
+/// Ramps with the same daytime value the post process gets, so dusk and dawn only sting a little.
+/// Roofs, trees and building shadows cover fully, the umbrella only partly.
+pub fn sun_exposure(
+    mut exposure: ResMut<SunExposure>,
+    mut player: Query<(&Transform, &Player, &mut Health, &AnimationController, Has<Slumber>)>,
+    shade: Res<ShadeCells>,
+    transformer: Res<TransformToGrid>,
+    day_cycle: Res<DayCycle>,
+    tunables: Res<Tunables>,
+    time: Res<Time>,
+) {
+    let Ok((transform, player, mut health, animation, is_sleeping)) = player.get_single_mut() else {return};
+    let sun = 1. - day_cycle.darkness;
+    let shaded = shade.is_shaded(&transformer.from_world_i32(transform.translation.xy()));
+    exposure.0 = if player.is_dead || is_sleeping || shaded || sun <= 0. {
+        0.
+    } else if animation.is_armed() {
+        sun * (1. - tunables.umbrella_shade)
+    } else {
+        sun
+    };
+    health.current -= exposure.0 * tunables.sun_damage * time.delta_seconds();
+}
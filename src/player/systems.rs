@@ -1,21 +1,24 @@
-use std::time::Duration;
 
 use bevy::prelude::*;
 use bevy_rapier2d::prelude::*;
 
 use crate::characters::animation::{spawn_player_animation_bundle, AnimationController, PartType};
 use crate::core::camera::plugin::CameraFollow;
-use crate::core::functions::{ExpDecay, TextureAtlasLayoutHandles};
-use crate::core::ui::PlayerUINode;
-use crate::npc::systems::RosesCollected;
+use crate::core::damage::{DamageTaken, Health, Invulnerable, Knockback};
+use crate::core::functions::TextureAtlasLayoutHandles;
+use crate::core::input::{Action, ActionState};
+use crate::core::tunables::Tunables;
 use crate::sounds::components::PlaySoundEvent;
-use crate::systems::DayCycle;
-use crate::PauseEvent;
-use bevy::math::{uvec2, vec2};
+use crate::systems::GameState;
 use pathfinding::num_traits::Signed;
 
+use super::abilities::{player_collision_groups, Abilities, AbilityId, AbilityRegistry, BatForm};
+use super::archetype::{Archetype, Archetypes, SelectedArchetype};
 use super::components::*;
-use super::upgrade_ui::{lvl_up, spawn_death_text, update_death_text};
+use super::coffin::{CoffinCheckpoint, ReviveAtCoffin, Slumber};
+use super::feeding::Feeding;
+use super::stats::RunStats;
+use super::upgrade_ui::update_death_text;
 
 pub const PLAYER_CG: u32 = 0b0000_0000_0000_0001;
 pub const NPC_CG: u32 = 0b0000_0000_0000_0010;
@@ -51,159 +54,123 @@ pub fn spawn_player_first_time(
     mut commands: Commands,
     asset_server: Res<AssetServer>,
     mut layout_handles: ResMut<TextureAtlasLayoutHandles>,
+    archetypes: Res<Archetypes>,
+    selected: Res<SelectedArchetype>,
+    tunables: Res<Tunables>,
 ) {
-    spawn_player(&mut commands, &asset_server, &mut layout_handles);
+    spawn_player(&mut commands, &asset_server, &mut layout_handles, &archetypes.get(selected.index), &tunables);
 }
 
 pub fn spawn_player(
     commands: &mut Commands,
     asset_server: &Res<AssetServer>,
     layout_handles: &mut ResMut<TextureAtlasLayoutHandles>,
-){
+    archetype: &Archetype,
+    tunables: &Tunables,
+) {
     let entity = spawn_player_animation_bundle(commands, asset_server, layout_handles);
     commands.entity(entity).insert((
         VisibilityBundle::default(),
         TransformBundle::from_transform(Transform::from_xyz(16., 16., -1.)),
         Name::new("Player"),
         CameraFollow{order: 0, speed: 10.},
-        Player::default(),
+        archetype.player(),
+        archetype.health(),
+        archetype.damageable(tunables.invulnerability),
         AnimationController::default(),
+    )).insert((
         RigidBody::Dynamic,
         LockedAxes::ROTATION_LOCKED_Z,
         Collider::ball(4.),
         ActiveEvents::COLLISION_EVENTS,
         Velocity::zero(),
         PlayerController::default(),
-        DashTimer {timer: Timer::new(Duration::from_secs_f32(0.35), TimerMode::Repeating)},
+        Abilities::default(),
         Sleeping::disabled(),
-        CollisionGroups::new(
-            Group::from_bits(PLAYER_CG).unwrap(),
-            Group::from_bits(BULLET_CG | STRUCTURES_CG | NPC_CG | RAYCASTABLE_STRUCT_CG).unwrap()
-        ),
+        player_collision_groups(0),
     ));
 }
 
+pub fn read_player_input(
+    actions: Res<ActionState>,
+    mut input: ResMut<PlayerInput>,
+) {
+    input.movement = actions.axis(Action::MoveAxis);
+    input.abilities = Action::ABILITY_SLOTS.into_iter().filter(|a| actions.just_pressed(*a)).collect();
+    input.bite = actions.just_pressed(Action::Bite);
+    input.pause = actions.just_pressed(Action::Pause);
+    input.menu_nav = actions.just_pressed(Action::MoveDown) as i32 - actions.just_pressed(Action::MoveUp) as i32;
+    input.menu_adjust = actions.just_pressed(Action::MoveRight) as i32 - actions.just_pressed(Action::MoveLeft) as i32;
+    input.confirm = actions.just_pressed(Action::Interact);
+    // set by the ui systems later in the frame
+    input.menu = None;
+}
+
+/// Walking and hunger, both pause while a moving ability (e.g. the dash) is active or while feeding.
 pub fn player_controller(
-    mut commands: Commands,
     mut player_q: Query<(&mut Velocity, &mut PlayerController,
-        &mut AnimationController, &mut DashTimer, &mut Player, Entity)>,
-    keyboard: Res<ButtonInput<KeyCode>>,
-    day_cycle: Res<DayCycle>,
+        &mut AnimationController, &Abilities, &Player, &mut Health, Has<Feeding>), Without<Slumber>>,
+    input: Res<PlayerInput>,
+    registry: Res<AbilityRegistry>,
+    tunables: Res<Tunables>,
     time: Res<Time>,
-    mut dash_dir: Local<Vec2>,
-    mut dash_cd: Local<f32>,
-    mut play_sound: EventWriter<PlaySoundEvent>,
 ) {
     if let Ok((mut character_controller, mut controller,
-        mut animation_controller, mut dash_timer,
-        mut player, player_entity)) = player_q.get_single_mut() {
+        mut animation_controller, abilities,
+        player, mut health, is_feeding)) = player_q.get_single_mut() {
     character_controller.linvel = Vec2::ZERO;
     if player.is_dead{return}
+    if abilities.is_moving(&registry) || is_feeding {return}
     let dt = time.delta_seconds();
 
+    let input_dir = input.movement;
+    let speed_mult = if abilities.is_active(AbilityId::BatForm) {tunables.bat_speed} else {1.};
+    let max_speed = player.max_speed * speed_mult;
 
-    *dash_cd += dt;
-    if dash_timer.timer.elapsed_secs() == 0. {
-        let input_dir = vec2(
-            keyboard.pressed(KeyCode::KeyD) as i32 as f32 - keyboard.pressed(KeyCode::KeyA) as i32 as f32,
-            keyboard.pressed(KeyCode::KeyW) as i32 as f32 - keyboard.pressed(KeyCode::KeyS) as i32 as f32
-        );
-        
-        controller.accumulated_velocity = controller.accumulated_velocity.move_towards(input_dir.normalize_or_zero() * player.max_speed, dt * player.accumulation_gain);
-        if controller.accumulated_velocity.length() > player.max_speed {controller.accumulated_velocity = controller.accumulated_velocity.normalize() * player.max_speed}
-        character_controller.linvel = controller.accumulated_velocity;
-    
-        if input_dir.x.abs() < 0.1 { // x axis is priotirized 
-            if input_dir.y.abs() > 0.1 {
-                if input_dir.y.is_positive(){animation_controller.turn_up()}
-                if input_dir.y.is_negative(){animation_controller.turn_down()}
-            }
-        } else {
-            if input_dir.x.is_positive(){animation_controller.turn_right()}
-            if input_dir.x.is_negative(){animation_controller.turn_left()}
-        }
-        if controller.accumulated_velocity.length() > 0.1 {
-            animation_controller.play_walk();
-        } else {
-            animation_controller.play_idle_priority(1);
-        }
-        player.hp -= dt * player.hunger_rate;
-        
-        if keyboard.just_pressed(KeyCode::ShiftLeft) {
-            if *dash_cd < player.dash_cd {
-                play_sound.send(PlaySoundEvent::DashCD);
-                return;
-            }
-            play_sound.send(PlaySoundEvent::Dash);
-            dash_timer.timer.set_duration(Duration::from_secs_f32(0.35));
-            dash_timer.timer.tick(Duration::from_secs_f32(dt * player.dash_tick));
-            *dash_cd = 0.;
-            *dash_cd += dt;
-            
-            *dash_dir = input_dir;
-            if day_cycle.is_night {
-                commands.entity(player_entity).insert(
-                    (CollisionGroups::new(
-                        Group::from_bits(PLAYER_CG).unwrap(),
-                        Group::from_bits(STRUCTURES_CG | NPC_CG | RAYCASTABLE_STRUCT_CG).unwrap()
-                    ),
-                    Sensor,)
-                );
-            } else {
-                commands.entity(player_entity).insert(
-                    CollisionGroups::new(
-                        Group::from_bits(PLAYER_CG).unwrap(),
-                        Group::from_bits(STRUCTURES_CG | RAYCASTABLE_STRUCT_CG).unwrap()
-                    ),
-                );
-            }
-        }
-    } else {
-        dash_timer.timer.tick(Duration::from_secs_f32(dt * player.dash_tick));
-        let t = dash_timer.timer.elapsed_secs();
-
-        let new_max = player.max_speed * g(t);
-        let new_gain = player.accumulation_gain * g(t);
+    // a half tilted stick walks at half speed
+    controller.accumulated_velocity = controller.accumulated_velocity.move_towards(input_dir.clamp_length_max(1.) * max_speed, dt * player.accumulation_gain * speed_mult);
+    if controller.accumulated_velocity.length() > max_speed {controller.accumulated_velocity = controller.accumulated_velocity.normalize() * max_speed}
+    character_controller.linvel = controller.accumulated_velocity;
 
-        controller.accumulated_velocity = controller.accumulated_velocity.move_towards(dash_dir.normalize_or_zero() * new_max, dt * new_gain);
-        if controller.accumulated_velocity.length() > new_max {controller.accumulated_velocity = controller.accumulated_velocity.normalize() * new_max}
-        character_controller.linvel = controller.accumulated_velocity;
-        
-        if dash_timer.timer.finished() {
-            dash_timer.timer.set_elapsed(Duration::from_secs_f32(0.));
-            commands.entity(player_entity).insert(
-            CollisionGroups::new(
-                Group::from_bits(PLAYER_CG).unwrap(),
-                Group::from_bits(BULLET_CG | STRUCTURES_CG | NPC_CG | RAYCASTABLE_STRUCT_CG).unwrap()
-            )).remove::<Sensor>();
+    if input_dir.x.abs() < 0.1 { // x axis is priotirized 
+        if input_dir.y.abs() > 0.1 {
+            if input_dir.y.is_positive(){animation_controller.turn_up()}
+            if input_dir.y.is_negative(){animation_controller.turn_down()}
         }
+    } else {
+        if input_dir.x.is_positive(){animation_controller.turn_right()}
+        if input_dir.x.is_negative(){animation_controller.turn_left()}
     }
+    if controller.accumulated_velocity.length() > 0.1 {
+        animation_controller.play_walk();
+    } else {
+        animation_controller.play_idle_priority(1);
+    }
+    health.current -= dt * player.hunger_rate;
     }
 }
 
-fn g(x: f32) -> f32 {
-    let x = 3. - 5. * x;
-    5. * std::f32::consts::E.powf(-(x - 1.639964).powf(2.)/(2.*0.800886f32.powf(2.)))
-}
-
+/// Hunger drains the health outside of damage events, so death is checked every frame.
+/// With a coffin checkpoint the player comes back there instead of dying.
 pub fn hit_player(
-    mut hit_player: EventReader<HitPlayer>,
-    mut player: Query<(&mut Player, &mut AnimationController)>,
+    mut taken: EventReader<DamageTaken>,
+    mut player: Query<(Entity, &Player, &Health, &mut AnimationController)>,
+    checkpoint: Res<CoffinCheckpoint>,
     mut kill_player: EventWriter<KillPlayer>,
+    mut revive: EventWriter<ReviveAtCoffin>,
 ) {
-    if let Ok((mut player, mut animation_controller)) = player.get_single_mut() {
-        for hit in hit_player.read() {
-            animation_controller.play_hurt();
-            if hit.dmg_type == 0 { // proj
-                player.hp -= player.max_hp * 0.1 * (1. - player.phys_res)
-            } else if hit.dmg_type == 1 { // civ
-                player.hp -= player.max_hp * 0.05 * (1. - player.phys_res)
-            } else if hit.dmg_type == 2 { // hun
-                player.hp -= 15. * (1. - player.phys_res)
+    if let Ok((entity, player, health, mut animation_controller)) = player.get_single_mut() {
+        for hit in taken.read() {
+            if hit.target == entity {
+                animation_controller.play_hurt();
             }
         }
-        if player.hp < 0. && !player.is_dead {
-            kill_player.send(KillPlayer {won: false});
+        if health.is_dead() && !player.is_dead {
+            if checkpoint.pos.is_some() {
+                revive.send(ReviveAtCoffin);
+            } else {
+                kill_player.send(KillPlayer {won: false});
+            }
         }
     }
 }
@@ -213,76 +180,102 @@ pub fn kill_player(
     mut kill_player: EventReader<KillPlayer>,
     mut play_sound: EventWriter<PlaySoundEvent>,
     mut commands: Commands,
-    asset_server: Res<AssetServer>,
-    mut death_timer: ResMut<DeathTimer>,
-    time: Res<Time>,
-    mut death_time: Query<&mut Text, With<DeathTime>>,
-    death_text: Query<Entity, With<DeathText>>,
-    roses: Res<RosesCollected>,
+    mut next_state: ResMut<NextState<GameState>>,
 ) {
-    let dt = time.delta_seconds();
-    let t = death_timer.timer.duration().as_secs_f32() - death_timer.timer.elapsed_secs();
-    let (entity, mut player) = player_entity.single_mut();
+    let Ok((entity, mut player)) = player_entity.get_single_mut() else {return};
     for event in kill_player.read() {
         if !event.won {
             play_sound.send(PlaySoundEvent::Kill);
         }
         commands.entity(entity).insert(Visibility::Hidden);
         player.is_dead = true;
-        death_timer.timer.tick(Duration::from_secs_f32(dt));
-        spawn_death_text(&mut commands, &asset_server, t, &roses, event.won);
+        next_state.set(if event.won {GameState::Victory} else {GameState::GameOver});
     }
-    if death_timer.timer.elapsed_secs() != 0. {
-        death_timer.timer.tick(Duration::from_secs_f32(dt));
-        update_death_text(t, &mut death_time);
-        if death_timer.timer.finished() {
-            commands.entity(entity).insert((
-                Visibility::Visible,
-                Transform::from_xyz(16., 16., 0.),
-                Player::default()
-            ));
-            death_timer.timer.set_elapsed(Duration::ZERO);
-            for entity in death_text.iter() {
-                commands.entity(entity).despawn();
-            }
+}
+
+/// Counts down the death screen on the real clock (the world one is paused), then shows the summary.
+pub fn tick_death_screen(
+    mut death_timer: ResMut<DeathTimer>,
+    mut death_time: Query<&mut Text, With<DeathTime>>,
+    mut next_state: ResMut<NextState<GameState>>,
+    time: Res<Time<bevy::time::Real>>,
+) {
+    death_timer.timer.tick(time.delta());
+    let t = death_timer.timer.duration().as_secs_f32() - death_timer.timer.elapsed_secs();
+    update_death_text(t, &mut death_time);
+    if death_timer.timer.just_finished() {
+        next_state.set(GameState::Summary);
+    }
+}
+
+/// Also runs when leaving the start screen, to apply the picked archetype.
+pub fn respawn_player(
+    mut player_entity: Query<(Entity, &mut AnimationController), With<Player>>,
+    mut bodies: Query<(&Parent, &PartType, &mut Handle<Image>, &mut Sprite)>,
+    archetypes: Res<Archetypes>,
+    selected: Res<SelectedArchetype>,
+    tunables: Res<Tunables>,
+    asset_server: Res<AssetServer>,
+    mut commands: Commands,
+) {
+    if let Ok((entity, mut animation)) = player_entity.get_single_mut() {
+        let archetype = archetypes.get(selected.index);
+        animation.become_vampire();
+        commands.entity(entity).insert((
+            Visibility::Visible,
+            Transform::from_xyz(16., 16., 0.),
+            Velocity::zero(),
+            PlayerController::default(),
+            Knockback::default(),
+            Abilities::default(),
+            archetype.player(),
+            archetype.health(),
+            archetype.damageable(tunables.invulnerability),
+            player_collision_groups(0),
+        ));
+        // an ability cut short by the restart never ran its end
+        commands.entity(entity).remove::<(Invulnerable, BatForm, Sensor, Feeding, Slumber)>();
+        for (parent, part, mut texture, mut sprite) in bodies.iter_mut() {
+            if parent.get() != entity || !matches!(part, PartType::Body{..}) {continue}
+            *texture = asset_server.load(&archetype.sprite);
+            sprite.color = archetype.tint();
         }
     }
 }
 
 pub fn kill_npc(
     mut kill_npc: EventReader<KillNpc>,
-    mut player: Query<&mut Player>,
+    mut player: Query<(&mut Player, &mut Health)>,
+    mut stats: ResMut<RunStats>,
 ) {
-    if let Ok(mut player) = player.get_single_mut() {
+    if let Ok((mut player, mut health)) = player.get_single_mut() {
         for kill in kill_npc.read() {
-            player.hp = (player.hp + player.hp_gain).clamp(0.0, player.max_hp);
-            if kill.npc_type == 0 { // civ
+            let xp = if kill.npc_type == 0 { // civ
                 player.score += 100.;
-                player.xp += player.xp_gain;
+                player.xp_gain
             } else if kill.npc_type == 1 { // hun
                 player.score += 500.;
-                player.xp += player.xp_gain * 3.;
-            }
+                player.xp_gain * 3.
+            } else {0.};
+            if kill.drained {continue}
+            health.heal(player.hp_gain);
+            player.xp += xp;
+            stats.xp_earned += xp;
         }
     }
 }
 
 pub fn manage_xp(
-    mut player: Query<&mut Player>,
+    mut player: Query<(&mut Player, &Health)>,
     mut play_sound: EventWriter<PlaySoundEvent>,
-    mut commands: Commands,
-    mut pause_event: EventWriter<PauseEvent>,
-    asset_server: Res<AssetServer>,
-    mut t: Local<bool>,
+    mut next_state: ResMut<NextState<GameState>>,
 ) {
-    if let Ok(mut player) = player.get_single_mut() {
+    if let Ok((mut player, health)) = player.get_single_mut() {
         if player.xp > player.max_xp {
-            player.xp -= player.max_hp;
+            player.xp -= health.max;
             player.max_xp *= 1.2;
             play_sound.send(PlaySoundEvent::LvlUp);
-            lvl_up(&mut commands, &asset_server);
-            pause_event.send(PauseEvent);
-            *t = true;
+            next_state.set(GameState::LevelUp);
         }
     }
 }
\ No newline at end of file
@@ -1,12 +1,52 @@
 use bevy::prelude::*;
 
-use crate::{npc::systems::RosesCollected, sounds::components::PlaySoundEvent, PauseEvent, NUM_ROSES};
+use crate::{core::{damage::{Damageable, Health}, rng::GameRng, tunables::Tunables}, npc::systems::RosesCollected, sounds::components::PlaySoundEvent, systems::GameState, NUM_ROSES};
 
-use super::components::{DeathText, DeathTime, ParentEntity, Player, UpgradeButton};
+use super::components::{DeathText, DeathTime, DeathTimer, MenuChoice, ParentEntity, Player, PlayerInput};
+use super::abilities::Abilities;
+use super::leaderboard::{Leaderboard, RunRanking};
+use super::upgrades::{Rarity, TakenUpgrades, UpgradeOffer, UpgradeRegistry};
+
+#[derive(Component)]
+pub struct LvlUpScreen;
+
+#[derive(Component)]
+pub struct LvlUpButton {
+    pub choice: MenuChoice,
+}
+
+/// Runs before the screen is spawned. With nothing left to take the level up is skipped.
+pub fn draw_upgrades(
+    registry: Res<UpgradeRegistry>,
+    taken: Res<TakenUpgrades>,
+    tunables: Res<Tunables>,
+    mut offer: ResMut<UpgradeOffer>,
+    mut rng: ResMut<GameRng>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    offer.choices = registry.draw(&taken, tunables.upgrade_choices, &mut rng);
+    if offer.choices.is_empty() {
+        next_state.set(GameState::Playing);
+    }
+}
+
+pub fn spawn_lvl_up_screen(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    registry: Res<UpgradeRegistry>,
+    offer: Res<UpgradeOffer>,
+    tunables: Res<Tunables>,
+) {
+    let rerolls = tunables.upgrade_rerolls.saturating_sub(offer.rerolls_used);
+    lvl_up(&mut commands, &asset_server, &registry, &offer, rerolls);
+}
 
 pub fn lvl_up(
     commands: &mut Commands,
     asset_server: &Res<AssetServer>,
+    registry: &UpgradeRegistry,
+    offer: &UpgradeOffer,
+    rerolls: u32,
 ) {
     let font = asset_server.load("fonts/Monocraft.ttf");
     let parent = commands.spawn((
@@ -25,17 +65,18 @@ pub fn lvl_up(
             ..default()
         },
         Name::new("LvlUpScreen"),
+        LvlUpScreen,
     )).id();
     let mut children = vec![];
     
-    children.push(spawn_button(commands, asset_server, font.clone_weak(), "Max HP + 10%", UpgradeButton::MaxHp, parent));
-    children.push(spawn_button(commands, asset_server, font.clone_weak(), "Armor + 10%", UpgradeButton::Armor, parent));
-    children.push(spawn_button(commands, asset_server, font.clone_weak(), "HP Gain + 10%", UpgradeButton::HpGain, parent));
-    children.push(spawn_button(commands, asset_server, font.clone_weak(), "XP Gain + 10%", UpgradeButton::XpGain, parent));
-    children.push(spawn_button(commands, asset_server, font.clone_weak(), "Speed + 10%", UpgradeButton::Speed, parent));
-    children.push(spawn_button(commands, asset_server, font.clone_weak(), "Dash CD - 8%", UpgradeButton::DashCD, parent));
-    children.push(spawn_button(commands, asset_server, font.clone_weak(), "Dash Time + 8%", UpgradeButton::DashTick, parent));
-    children.push(spawn_button(commands, asset_server, font.clone_weak(), "Hunger Rate - 20%", UpgradeButton::HungerRate, parent));
+    for id in &offer.choices {
+        let Some(upgrade) = registry.get(*id) else {continue};
+        children.push(spawn_button(commands, asset_server, font.clone_weak(), upgrade.label, upgrade.rarity.color(), MenuChoice::Upgrade(*id), parent));
+    }
+    if rerolls > 0 {
+        let text = format!("Reroll ({})", rerolls);
+        children.push(spawn_button(commands, asset_server, font.clone_weak(), &text, Rarity::Common.color(), MenuChoice::Reroll, parent));
+    }
 
     for child in children {
         commands.entity(parent).add_child(child);
@@ -47,7 +88,8 @@ fn spawn_button(
     asset_server: &Res<AssetServer>,
     font: Handle<Font>,
     text: &str,
-    upgrade_type: UpgradeButton,
+    color: Color,
+    choice: MenuChoice,
     parent: Entity,
 ) -> Entity {
     commands.spawn((ButtonBundle {
@@ -64,7 +106,7 @@ fn spawn_button(
         image: UiImage::from(asset_server.load("button.png")),
         ..default()
     },
-    upgrade_type,
+    LvlUpButton { choice },
     ParentEntity { entity: parent},
     )).with_children(|parent| {
         parent.spawn(TextBundle {
@@ -74,7 +116,7 @@ fn spawn_button(
                 ..default()
             },
             text: Text {
-                sections: vec![TextSection::new(text, TextStyle { font, font_size: 16., color: Color::srgb_u8(169, 96, 45) })],
+                sections: vec![TextSection::new(text, TextStyle { font, font_size: 16., color })],
                 ..default()
             },
             ..default()
@@ -83,76 +125,120 @@ fn spawn_button(
 }
 
 pub fn interact_upgrade_button(
-    mut commands: Commands,
-    mut button_q: Query<(&Interaction, &mut UiImage, &UpgradeButton, &ParentEntity), Changed<Interaction>>,
-    mut player: Query<&mut Player>,
-    mut pause_event: EventWriter<PauseEvent>,
+    mut button_q: Query<(&Interaction, &mut UiImage, &LvlUpButton), Changed<Interaction>>,
+    mut input: ResMut<PlayerInput>,
     mut play_sound: EventWriter<PlaySoundEvent>,
     asset_server: Res<AssetServer>,
 ) {
-    if let Ok(mut player) = player.get_single_mut() {
-        if let Ok((interaction, mut image,
-            upgrade_type, parent_entity)) = button_q.get_single_mut() {
-            match *interaction {
-                Interaction::Pressed => {
-                    match *upgrade_type {
-                        UpgradeButton::MaxHp => {
-                            player.max_hp *= 1.1;
-                        },
-                        UpgradeButton::Armor => {
-                            player.phys_res *= 1.1;
-                        },
-                        UpgradeButton::HpGain => {
-                            player.hp_gain *= 1.1;
-                        },
-                        UpgradeButton::XpGain => {
-                            player.xp_gain *= 1.1;
-                        },
-                        UpgradeButton::Speed => {
-                            player.max_speed *= 1.1;
-                            player.accumulation_gain *= 1.1;
-                        },
-                        UpgradeButton::HungerRate => {
-                            player.hunger_rate *= 0.8;
-                        },
-                        UpgradeButton::DashCD => {
-                            player.dash_cd *= 0.92;
-                        },
-                        UpgradeButton::DashTick => {
-                            player.dash_tick *= 0.92;
-                        },
-                        
-                    }
-                    commands.entity(parent_entity.entity).despawn_recursive();
-                    play_sound.send(PlaySoundEvent::Selected);
-                    pause_event.send(PauseEvent);
-                }
-                Interaction::Hovered => {
-                    play_sound.send(PlaySoundEvent::Select);
-                    *image = UiImage::from(asset_server.load("select_button.png"));
-                }
-                Interaction::None => {
-                    *image = UiImage::from(asset_server.load("button.png"));
-                }
+    if let Ok((interaction, mut image, button)) = button_q.get_single_mut() {
+        match *interaction {
+            Interaction::Pressed => {
+                input.menu = Some(button.choice);
+            }
+            Interaction::Hovered => {
+                play_sound.send(PlaySoundEvent::Select);
+                *image = UiImage::from(asset_server.load("select_button.png"));
+            }
+            Interaction::None => {
+                *image = UiImage::from(asset_server.load("button.png"));
             }
         }
     }
 }
 
+pub fn apply_upgrade(
+    input: Res<PlayerInput>,
+    registry: Res<UpgradeRegistry>,
+    offer: Res<UpgradeOffer>,
+    mut taken: ResMut<TakenUpgrades>,
+    mut player: Query<(&mut Player, &mut Health, &mut Damageable, &mut Abilities)>,
+    mut next_state: ResMut<NextState<GameState>>,
+    mut play_sound: EventWriter<PlaySoundEvent>,
+) {
+    let Some(MenuChoice::Upgrade(id)) = input.menu else {return};
+    // a replay recorded against other draws must not apply what isn't offered
+    if !offer.choices.contains(&id) {return}
+    let Some(upgrade) = registry.get(id) else {return};
+    if let Ok((mut player, mut health, mut damageable, mut abilities)) = player.get_single_mut() {
+        (upgrade.apply)(&mut player, &mut health, &mut damageable);
+        if let Some(ability) = upgrade.unlocks {
+            abilities.unlock(ability);
+        }
+        taken.taken.push(id);
+        play_sound.send(PlaySoundEvent::Selected);
+        next_state.set(GameState::Playing);
+    }
+}
+
+pub fn reroll_upgrades(
+    mut commands: Commands,
+    input: Res<PlayerInput>,
+    asset_server: Res<AssetServer>,
+    registry: Res<UpgradeRegistry>,
+    taken: Res<TakenUpgrades>,
+    tunables: Res<Tunables>,
+    mut offer: ResMut<UpgradeOffer>,
+    mut rng: ResMut<GameRng>,
+    screen: Query<Entity, With<LvlUpScreen>>,
+    mut play_sound: EventWriter<PlaySoundEvent>,
+) {
+    if input.menu != Some(MenuChoice::Reroll) || offer.rerolls_used >= tunables.upgrade_rerolls {return}
+    offer.rerolls_used += 1;
+    offer.choices = registry.draw(&taken, tunables.upgrade_choices, &mut rng);
+    for entity in screen.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+    let rerolls = tunables.upgrade_rerolls - offer.rerolls_used;
+    lvl_up(&mut commands, &asset_server, &registry, &offer, rerolls);
+    play_sound.send(PlaySoundEvent::Selected);
+}
+
+pub fn spawn_game_over_screen(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut death_timer: ResMut<DeathTimer>,
+    roses: Res<RosesCollected>,
+    registry: Res<UpgradeRegistry>,
+    taken: Res<TakenUpgrades>,
+    leaderboard: Option<Res<Leaderboard>>,
+    ranking: Option<Res<RunRanking>>,
+) {
+    death_timer.timer.reset();
+    let t = death_timer.timer.duration().as_secs_f32();
+    spawn_death_text(&mut commands, &asset_server, t, &roses, &taken.summary(&registry), false);
+    if let (Some(leaderboard), Some(ranking)) = (leaderboard, ranking) {
+        spawn_leaderboard_text(&mut commands, &asset_server, &leaderboard, &ranking);
+    }
+}
+
+pub fn spawn_victory_screen(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut death_timer: ResMut<DeathTimer>,
+    roses: Res<RosesCollected>,
+    registry: Res<UpgradeRegistry>,
+    taken: Res<TakenUpgrades>,
+    leaderboard: Option<Res<Leaderboard>>,
+    ranking: Option<Res<RunRanking>>,
+) {
+    death_timer.timer.reset();
+    let t = death_timer.timer.duration().as_secs_f32();
+    spawn_death_text(&mut commands, &asset_server, t, &roses, &taken.summary(&registry), true);
+    if let (Some(leaderboard), Some(ranking)) = (leaderboard, ranking) {
+        spawn_leaderboard_text(&mut commands, &asset_server, &leaderboard, &ranking);
+    }
+}
+
 pub fn spawn_death_text(
     commands: &mut Commands,
     asset_server: &Res<AssetServer>,
     time: f32,
     roses_collected: &Res<RosesCollected>,
+    upgrades: &[String],
     won: bool,
 ) {
     let font = asset_server.load("fonts/Monocraft.ttf");
-    let text;
-    if won {
-        text = "You Won"
-    } else {
-        text = "You Died"
-    }
+    let text = if won {"You Won"} else {"You Died"};
     commands.spawn((TextBundle {
         style: Style {
             top: Val::Percent(20.),
@@ -206,12 +292,7 @@ pub fn spawn_death_text(
         }, DeathText));
     }
     
-    let collected;
-    if won {
-        collected = NUM_ROSES
-    } else {
-        collected = roses_collected.collected;
-    }
+    let collected = if won {NUM_ROSES} else {roses_collected.collected};
     commands.spawn((TextBundle {
         style: Style {
             top: Val::Percent(80.),
@@ -224,8 +305,70 @@ pub fn spawn_death_text(
         },
         ..default()
     }, DeathText));
+    if !upgrades.is_empty() {
+        commands.spawn((TextBundle {
+            style: Style {
+                top: Val::Percent(90.),
+                justify_self: JustifySelf::Center,
+                ..default()
+            },
+            text: Text {
+                sections: vec![TextSection::new(upgrades.join(", "), TextStyle { font: font.clone(), font_size: 16., color: Color::WHITE })],
+                ..default()
+            },
+            ..default()
+        }, DeathText));
+    }
 }
 
+/// Top runs on the left of the death screen, the one that just ended highlighted.
+pub fn spawn_leaderboard_text(
+    commands: &mut Commands,
+    asset_server: &Res<AssetServer>,
+    leaderboard: &Leaderboard,
+    ranking: &RunRanking,
+) {
+    let font = asset_server.load("fonts/Monocraft.ttf");
+    let highlight = Color::srgb_u8(230, 180, 60);
+    let headline = if ranking.is_best() {
+        (format!("New Best: {}", ranking.record.score as i32), highlight)
+    } else {
+        let best = leaderboard.runs.first().map_or(0., |r| r.score);
+        (format!("Score: {}  Best: {}", ranking.record.score as i32, best as i32), Color::WHITE)
+    };
+    commands.spawn((TextBundle {
+        style: Style {
+            top: Val::Percent(10.),
+            justify_self: JustifySelf::Center,
+            ..default()
+        },
+        text: Text {
+            sections: vec![TextSection::new(headline.0, TextStyle { font: font.clone(), font_size: 30., color: headline.1 })],
+            ..default()
+        },
+        ..default()
+    }, DeathText));
+    let sections = leaderboard.runs.iter().take(5).enumerate().map(|(i, run)| {
+        let color = if ranking.rank == Some(i) {highlight} else {Color::WHITE};
+        TextSection::new(
+            format!("{}. {}  night {}  {}\n", i + 1, run.score as i32, run.nights, run.date()),
+            TextStyle { font: font.clone(), font_size: 16., color },
+        )
+    }).collect();
+    commands.spawn((TextBundle {
+        style: Style {
+            position_type: PositionType::Absolute,
+            top: Val::Percent(20.),
+            left: Val::Percent(2.),
+            ..default()
+        },
+        text: Text {
+            sections,
+            ..default()
+        },
+        ..default()
+    }, DeathText));
+}
 
 pub fn update_death_text(
     time: f32,
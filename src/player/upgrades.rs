@@ -0,0 +1,200 @@
+use bevy::prelude::*;
+use rand::Rng;
+
+use crate::core::{damage::{Damageable, Health, MAX_RESISTANCE}, rng::GameRng};
+
+use super::{abilities::AbilityId, components::{Player, UpgradeButton}};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Rarity {
+    Common,
+    Rare,
+    Epic,
+}
+
+impl Rarity {
+    pub fn weight(&self) -> u32 {
+        match self {
+            Rarity::Common => 10,
+            Rarity::Rare => 4,
+            Rarity::Epic => 1,
+        }
+    }
+
+    pub fn color(&self) -> Color {
+        match self {
+            Rarity::Common => Color::srgb_u8(169, 96, 45),
+            Rarity::Rare => Color::srgb_u8(60, 90, 160),
+            Rarity::Epic => Color::srgb_u8(130, 50, 150),
+        }
+    }
+}
+
+pub struct Upgrade {
+    pub id: UpgradeButton,
+    pub label: &'static str,
+    pub rarity: Rarity,
+    pub max_stacks: u32,
+    /// Upgrades that must be taken (at least this many times) before this one is offered.
+    pub requires: &'static [(UpgradeButton, u32)],
+    /// Ability bound to a free slot when taken.
+    pub unlocks: Option<AbilityId>,
+    pub apply: fn(&mut Player, &mut Health, &mut Damageable),
+}
+
+#[derive(Resource)]
+pub struct UpgradeRegistry {
+    pub upgrades: Vec<Upgrade>,
+}
+
+impl Default for UpgradeRegistry {
+    fn default() -> Self {
+        UpgradeRegistry {upgrades: vec![
+            Upgrade {
+                id: UpgradeButton::MaxHp,
+                label: "Max HP + 10%",
+                rarity: Rarity::Common,
+                max_stacks: 10,
+                requires: &[],
+                unlocks: None,
+                apply: |_, h, _| h.max *= 1.1,
+            },
+            Upgrade {
+                id: UpgradeButton::Armor,
+                label: "Armor + 10%",
+                rarity: Rarity::Rare,
+                max_stacks: 8,
+                requires: &[],
+                unlocks: None,
+                // physical only, holy damage ignores armor
+                apply: |_, _, d| {
+                    d.resistances.projectile = (d.resistances.projectile * 1.1).min(MAX_RESISTANCE);
+                    d.resistances.melee = (d.resistances.melee * 1.1).min(MAX_RESISTANCE);
+                },
+            },
+            Upgrade {
+                id: UpgradeButton::HpGain,
+                label: "HP Gain + 10%",
+                rarity: Rarity::Common,
+                max_stacks: 10,
+                requires: &[],
+                unlocks: None,
+                apply: |p, _, _| p.hp_gain *= 1.1,
+            },
+            Upgrade {
+                id: UpgradeButton::XpGain,
+                label: "XP Gain + 10%",
+                rarity: Rarity::Common,
+                max_stacks: 10,
+                requires: &[],
+                unlocks: None,
+                apply: |p, _, _| p.xp_gain *= 1.1,
+            },
+            Upgrade {
+                id: UpgradeButton::Speed,
+                label: "Speed + 10%",
+                rarity: Rarity::Rare,
+                max_stacks: 5,
+                requires: &[],
+                unlocks: None,
+                apply: |p, _, _| {
+                    p.max_speed *= 1.1;
+                    p.accumulation_gain *= 1.1;
+                },
+            },
+            Upgrade {
+                id: UpgradeButton::DashCD,
+                label: "Dash CD - 8%",
+                rarity: Rarity::Common,
+                max_stacks: 6,
+                requires: &[],
+                unlocks: None,
+                apply: |p, _, _| p.dash_cd *= 0.92,
+            },
+            Upgrade {
+                id: UpgradeButton::DashTick,
+                label: "Dash Time + 8%",
+                rarity: Rarity::Rare,
+                max_stacks: 5,
+                requires: &[(UpgradeButton::DashCD, 1)],
+                unlocks: None,
+                apply: |p, _, _| p.dash_tick *= 0.92,
+            },
+            Upgrade {
+                id: UpgradeButton::HungerRate,
+                label: "Hunger Rate - 20%",
+                rarity: Rarity::Epic,
+                max_stacks: 3,
+                requires: &[(UpgradeButton::HpGain, 1)],
+                unlocks: None,
+                apply: |p, _, _| p.hunger_rate *= 0.8,
+            },
+            Upgrade {
+                id: UpgradeButton::BatForm,
+                label: "Bat Form",
+                rarity: Rarity::Epic,
+                max_stacks: 1,
+                requires: &[],
+                unlocks: Some(AbilityId::BatForm),
+                apply: |_, _, _| {},
+            },
+        ]}
+    }
+}
+
+impl UpgradeRegistry {
+    pub fn get(&self, id: UpgradeButton) -> Option<&Upgrade> {
+        self.upgrades.iter().find(|u| u.id == id)
+    }
+
+    /// Draws up to `count` different upgrades that can still be taken, weighted by rarity.
+    pub fn draw(&self, taken: &TakenUpgrades, count: usize, rng: &mut GameRng) -> Vec<UpgradeButton> {
+        let mut pool: Vec<&Upgrade> = self.upgrades.iter().filter(|u| {
+            taken.stacks(u.id) < u.max_stacks
+                && u.requires.iter().all(|(id, stacks)| taken.stacks(*id) >= *stacks)
+        }).collect();
+        let mut drawn = vec![];
+        while drawn.len() < count && !pool.is_empty() {
+            let total: u32 = pool.iter().map(|u| u.rarity.weight()).sum();
+            let mut roll = rng.upgrades().gen_range(0..total);
+            let mut i = 0;
+            while roll >= pool[i].rarity.weight() {
+                roll -= pool[i].rarity.weight();
+                i += 1;
+            }
+            drawn.push(pool.remove(i).id);
+        }
+        drawn
+    }
+}
+
+/// Upgrades taken this run, in the order they were picked.
+#[derive(Resource, Default, Clone)]
+pub struct TakenUpgrades {
+    pub taken: Vec<UpgradeButton>,
+}
+
+impl TakenUpgrades {
+    pub fn stacks(&self, id: UpgradeButton) -> u32 {
+        self.taken.iter().filter(|t| **t == id).count() as u32
+    }
+
+    /// Each upgrade once with its stack count, e.g. `Armor + 10% x2`.
+    pub fn summary(&self, registry: &UpgradeRegistry) -> Vec<String> {
+        let mut seen: Vec<UpgradeButton> = vec![];
+        for id in &self.taken {
+            if !seen.contains(id) {seen.push(*id)}
+        }
+        seen.iter().filter_map(|id| {
+            let label = registry.get(*id)?.label;
+            Some(format!("{} x{}", label, self.stacks(*id)))
+        }).collect()
+    }
+}
+
+/// The choices on the current level up screen.
+#[derive(Resource, Default, Clone)]
+pub struct UpgradeOffer {
+    pub choices: Vec<UpgradeButton>,
+    pub rerolls_used: u32,
+}
@@ -0,0 +1,53 @@
+use bevy::prelude::*;
+use bevy_light_2d::light::PointLight2d;
+use bevy_rapier2d::prelude::Velocity;
+
+use crate::{core::tunables::Tunables, map::tilemap::LightEmitter, systems::DayCycle};
+
+use super::{abilities::{Abilities, AbilityRegistry}, coffin::Slumber, components::Player};
+
+/// Never fully invisible, someone bumping into the player still notices.
+const MIN_VISIBILITY: f32 = 0.1;
+const MAX_VISIBILITY: f32 = 1.5;
+
+/// How easy the player is to spot this frame, npc sight ranges are multiplied by it.
+/// 1 is standing still in daylight.
+#[derive(Resource, Clone, Copy, Debug)]
+pub struct PlayerVisibility(pub f32);
+
+impl Default for PlayerVisibility {
+    fn default() -> Self {
+        PlayerVisibility(1.)
+    }
+}
+
+/// Daylight, or the dark lit by nearby torches, campfires and windows,
+/// plus running and dashing. Nobody sees a player asleep in a coffin.
+pub fn update_player_visibility(
+    mut visibility: ResMut<PlayerVisibility>,
+    player: Query<(&Transform, &Velocity, &Player, &Abilities, Has<Slumber>)>,
+    lights: Query<(&GlobalTransform, &PointLight2d), With<LightEmitter>>,
+    registry: Res<AbilityRegistry>,
+    day_cycle: Res<DayCycle>,
+    tunables: Res<Tunables>,
+) {
+    let Ok((transform, velocity, player, abilities, is_sleeping)) = player.get_single() else {return};
+    // shut in a coffin
+    if is_sleeping {
+        visibility.0 = 0.;
+        return;
+    }
+    let pos = transform.translation.xy();
+    let lit: f32 = lights.iter()
+        .map(|(light_transform, light)| {
+            let dist = light_transform.translation().xy().distance(pos);
+            if dist >= light.radius {return 0.}
+            light.intensity * (1. - dist / light.radius)
+        })
+        .sum();
+    let ambient = tunables.dark_visibility + (1. - tunables.dark_visibility) * (1. - day_cycle.darkness);
+    let light = (ambient + lit * tunables.light_visibility).min(1.);
+    let speed = (velocity.linvel.length() / player.max_speed).min(1.);
+    let dash = if abilities.is_moving(&registry) {tunables.dash_visibility} else {0.};
+    visibility.0 = (light + speed * tunables.move_visibility + dash).clamp(MIN_VISIBILITY, MAX_VISIBILITY);
+}
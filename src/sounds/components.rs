@@ -1,4 +1,3 @@
-use bevy_kira_audio::prelude::*;
 use bevy::prelude::*;
 
 #[derive(Resource)]
@@ -24,7 +23,7 @@ pub struct AudioHandles {
     pub dash_cd: Handle<bevy_kira_audio::AudioSource>,
 }
 
-#[derive(Event)]
+#[derive(Event, Clone, Copy)]
 pub enum PlaySoundEvent {
     LvlUp,
     Dash,
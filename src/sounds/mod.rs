@@ -19,7 +19,7 @@ impl Plugin for AudioPlugin {
         .insert_resource(AudioHandles::default())
         .add_event::<PlaySoundEvent>()
         .add_systems(Startup, load_audio)
-        .add_systems(Update, (manage_background, play_sounds))
+        .add_systems(Update, (apply_volume_settings, manage_background, play_sounds))
         ;
     }
 }
\ No newline at end of file
@@ -2,9 +2,9 @@ use std::time::Duration;
 
 use bevy_kira_audio::prelude::*;
 use bevy::prelude::*;
-use rand::{thread_rng, Rng};
+use rand::Rng;
 
-use crate::{DayCycle, TRANSLATION_DURATION};
+use crate::{core::{rng::GameRng, settings::Settings, tunables::Tunables}, DayCycle};
 
 use super::components::*;
 
@@ -39,19 +39,32 @@ pub fn load_audio(
     night_channel.play(audio_handles.night.clone_weak())
     .start_from(0.)
     .fade_in(AudioTween::new(Duration::from_secs_f32(1.), AudioEasing::OutPowf(2.)))
-    .with_volume(1.)
     ;
 }
 
+/// Volumes live on the channels, so they also reach sounds that are already playing.
+pub fn apply_volume_settings(
+    settings: Res<Settings>,
+    day_channel: Res<AudioChannel<DayChannel>>,
+    night_channel: Res<AudioChannel<NightChannel>>,
+    sfx_channel: Res<AudioChannel<SfxChannel>>,
+) {
+    if !settings.is_changed() {return}
+    day_channel.set_volume(settings.day_volume as f64);
+    night_channel.set_volume(settings.night_volume as f64);
+    sfx_channel.set_volume(settings.sfx_volume as f64);
+}
+
 pub fn manage_background(
     day_channel: Res<AudioChannel<DayChannel>>,
     night_channel: Res<AudioChannel<NightChannel>>,
     day_cycle: Res<DayCycle>,
     audio_handles: Res<AudioHandles>,
+    tunables: Res<Tunables>,
     mut prev_state: Local<bool>
 ) {
     if day_cycle.is_translating && *prev_state != day_cycle.is_translating {
-        let dur = Duration::from_secs_f32(TRANSLATION_DURATION);
+        let dur = Duration::from_secs_f32(tunables.translation_duration);
         let easing = AudioEasing::OutPowf(2.);
         
         if day_cycle.is_night { // translating into day
@@ -62,7 +75,6 @@ pub fn manage_background(
             day_channel.play(audio_handles.day.clone_weak())
             .start_from(0.)
             .fade_in(AudioTween::new(dur, easing))
-            .with_volume(0.4)
             ;
         } else { // translating into night
             day_channel.stop()
@@ -72,7 +84,6 @@ pub fn manage_background(
             night_channel.play(audio_handles.night.clone_weak())
             .start_from(0.)
             .fade_in(AudioTween::new(dur, easing))
-            .with_volume(1.)
             ;
         }
     }
@@ -83,8 +94,9 @@ pub fn play_sounds(
     audio_handles: Res<AudioHandles>,
     sfx_channel: Res<AudioChannel<SfxChannel>>,
     mut sound_events: EventReader<PlaySoundEvent>,
+    mut rng: ResMut<GameRng>,
 ) {
-    let mut rng = thread_rng();
+    let rng = rng.audio();
     for sound in sound_events.read() {
         match *sound {
             PlaySoundEvent::Dash => {
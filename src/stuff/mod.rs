@@ -1,11 +1,11 @@
 use std::{f32::consts::PI, time::Duration};
 
-use bevy::{ecs::system::EntityCommands, math::{uvec2, vec3}, prelude::*};
+use bevy::{math::{uvec2, vec3}, prelude::*};
 use bevy_easings::*;
-use bevy_rapier2d::prelude::Velocity;
 use rand::Rng;
 
-use crate::core::{despawn_lifetime::DespawnTimer, functions::{ExpDecay, TextureAtlasLayoutHandles}};
+use crate::characters::animation::BAT_FRAMES;
+use crate::core::{despawn_lifetime::DespawnTimer, functions::{ExpDecay, TextureAtlasLayoutHandles}, rng::GameRng};
 
 pub enum SimpleAnimatedTexture{
     HeartGain,
@@ -29,7 +29,7 @@ pub fn simple_anim_update(
 ){
     let dt = time.delta_seconds();
     for (mut anim_type, mut atlas) in to_anim.iter_mut(){
-        anim_type.timer.tick(Duration::from_secs_f32(dt.into()));
+        anim_type.timer.tick(Duration::from_secs_f32(dt));
 
         let frames = match anim_type.effect {
             SimpleAnimatedTexture::HeartGain => 2,
@@ -174,6 +174,10 @@ pub fn animated_soul_bundle(asset_server: &Res<AssetServer>, atlas_handles: &mut
 #[derive(Component)]
 pub struct Stake;
 
+/// On a body left by a kill until an npc finds it.
+#[derive(Component)]
+pub struct Corpse;
+
 pub fn stake_bundle(asset_server: &Res<AssetServer>, atlas_handles: &mut ResMut<TextureAtlasLayoutHandles>, direction: Vec2) -> impl Bundle {
     let angle = direction.to_angle() + PI * 0.75;
     (
@@ -196,20 +200,22 @@ pub fn spawn_cililian_body(
     commands: &mut Commands,
     layout_handles: &mut ResMut<TextureAtlasLayoutHandles>,
     asset_server: &Res<AssetServer>,
+    rng: &mut GameRng,
     pos: Vec3,
 ) -> Entity {
+    let rng = rng.cosmetic();
 
     let max_offset = 4.;
     let start = pos + vec3(
-        rand::random::<f32>() * max_offset * 2. - max_offset,
-        rand::random::<f32>() * max_offset * 2. - max_offset,
+        rng.gen::<f32>() * max_offset * 2. - max_offset,
+        rng.gen::<f32>() * max_offset * 2. - max_offset,
         0.
     );
-    let flipped = rand::thread_rng().gen_bool(0.5);
+    let flipped = rng.gen_bool(0.5);
     let offset = if flipped{vec3(-2., 0., 0.)} else {vec3(2., 0., 0.)};
     commands.spawn(animated_soul_bundle(asset_server, layout_handles))
     .insert(Transform::from_translation(offset+vec3(0., 8., 10.) + start).ease_to(
-        Transform::from_translation(offset+start+vec3(0., 12. + rand::thread_rng().gen::<f32>() * 5., 1.)),
+        Transform::from_translation(offset+start+vec3(0., 12. + rng.gen::<f32>() * 5., 1.)),
         EaseFunction::ExponentialOut,
         EasingType::Once {
             duration: std::time::Duration::from_secs(1),
@@ -220,6 +226,7 @@ pub fn spawn_cililian_body(
         TransformBundle::default(),
         VisibilityBundle::default(),
         DespawnTimer::seconds(5.),
+        Corpse,
     ))
     .insert(Transform::from_translation(vec3(0., -1., -2.) + start).with_scale(vec3(if flipped{-1.} else {1.}, 1., 0.)))
     .with_children(|commands| {
@@ -245,19 +252,21 @@ pub fn spawn_hunter_body(
     commands: &mut Commands,
     layout_handles: &mut ResMut<TextureAtlasLayoutHandles>,
     asset_server: &Res<AssetServer>,
+    rng: &mut GameRng,
     pos: Vec3,
 ) -> Entity {
+    let rng = rng.cosmetic();
     let max_offset = 4.;
     let start = pos + vec3(
-        rand::random::<f32>() * max_offset * 2. - max_offset,
-        rand::random::<f32>() * max_offset * 2. - max_offset,
+        rng.gen::<f32>() * max_offset * 2. - max_offset,
+        rng.gen::<f32>() * max_offset * 2. - max_offset,
         0.
     );
-    let flipped = rand::thread_rng().gen_bool(0.5);
+    let flipped = rng.gen_bool(0.5);
     let offset = if flipped{vec3(-2., 0., 0.)} else {vec3(2., 0., 0.)};
     commands.spawn(animated_soul_bundle(asset_server, layout_handles))
     .insert(Transform::from_translation(offset+vec3(0., 8., 10.) + start).ease_to(
-        Transform::from_translation(offset+start+vec3(0., 12. + rand::thread_rng().gen::<f32>() * 5., 0.)),
+        Transform::from_translation(offset+start+vec3(0., 12. + rng.gen::<f32>() * 5., 0.)),
         EaseFunction::ExponentialOut,
         EasingType::Once {
             duration: std::time::Duration::from_secs(1),
@@ -268,6 +277,7 @@ pub fn spawn_hunter_body(
         TransformBundle::default(),
         VisibilityBundle::default(),
         DespawnTimer::seconds(5.),
+        Corpse,
     ))
     .insert(Transform::from_translation(vec3(0., -3., -2.) + start).with_scale(vec3(if flipped{-1.} else {1.}, 1., 1.)))
     .with_children(|commands| {
@@ -293,13 +303,15 @@ pub fn spawn_question_particle(
     commands: &mut Commands,
     layout_handles: &mut ResMut<TextureAtlasLayoutHandles>,
     asset_server: &Res<AssetServer>,
+    rng: &mut GameRng,
     pos: Vec3,
 ){
+    let rng = rng.cosmetic();
     let lifetime = 2.;
     let max_offset = 2.;
     let start = pos+vec3(
-        rand::random::<f32>() * max_offset * 2. - max_offset,
-        rand::random::<f32>() * max_offset * 2. - max_offset,
+        rng.gen::<f32>() * max_offset * 2. - max_offset,
+        rng.gen::<f32>() * max_offset * 2. - max_offset,
         0.
     );
     commands.spawn((
@@ -311,10 +323,10 @@ pub fn spawn_question_particle(
     .with_children(|commands| {
         commands.spawn((
             Name::new("Particle"),
-            emotion_bundle(asset_server, layout_handles, rand::thread_rng().gen_range(0..3) + 6),
-            Transform::from_translation(vec3(0., 0., 0.)).with_rotation(Quat::from_rotation_z(rand::thread_rng().gen::<f32>() - 0.5)).with_scale(Vec3::splat(0.5))
+            emotion_bundle(asset_server, layout_handles, rng.gen_range(0..3) + 6),
+            Transform::from_translation(vec3(0., 0., 0.)).with_rotation(Quat::from_rotation_z(rng.gen::<f32>() - 0.5)).with_scale(Vec3::splat(0.5))
                 .ease_to(
-                    Transform::from_translation(vec3(rand::thread_rng().gen::<f32>() * 3. - 1.5, 4. + rand::thread_rng().gen::<f32>() * 5., 0.)).with_rotation(Quat::from_rotation_z(rand::thread_rng().gen::<f32>() - 0.5))
+                    Transform::from_translation(vec3(rng.gen::<f32>() * 3. - 1.5, 4. + rng.gen::<f32>() * 5., 0.)).with_rotation(Quat::from_rotation_z(rng.gen::<f32>() - 0.5))
                     .with_scale(Vec3::splat(1.5)),
                     EaseFunction::ExponentialOut,
                     EasingType::Once {
@@ -337,16 +349,18 @@ pub fn spawn_angry_particle(
     commands: &mut Commands,
     layout_handles: &mut ResMut<TextureAtlasLayoutHandles>,
     asset_server: &Res<AssetServer>,
+    rng: &mut GameRng,
     pos: Vec3,
 ){
+    let rng = rng.cosmetic();
     let max_offset = 2.;
     let start = pos+vec3(
-        rand::random::<f32>() * max_offset * 2. - max_offset,
-        rand::random::<f32>() * max_offset * 2. - max_offset,
+        rng.gen::<f32>() * max_offset * 2. - max_offset,
+        rng.gen::<f32>() * max_offset * 2. - max_offset,
         0.
     );
 
-    let flipped = rand::thread_rng().gen::<bool>();
+    let flipped = rng.gen::<bool>();
     commands.spawn((
         TransformBundle::default(),
         VisibilityBundle::default(),
@@ -356,10 +370,10 @@ pub fn spawn_angry_particle(
     .with_children(|commands| {
         commands.spawn((
             Name::new("Particle"),
-            emotion_bundle(asset_server, layout_handles, rand::thread_rng().gen_range(0..3)),
-            Transform::from_translation(vec3(0., 0., 0.)).with_rotation(Quat::from_rotation_z(rand::thread_rng().gen::<f32>() - 0.5)).with_scale(Vec3::splat(0.5) * vec3(if flipped{-1.} else {1.}, 1., 1.))
+            emotion_bundle(asset_server, layout_handles, rng.gen_range(0..3)),
+            Transform::from_translation(vec3(0., 0., 0.)).with_rotation(Quat::from_rotation_z(rng.gen::<f32>() - 0.5)).with_scale(Vec3::splat(0.5) * vec3(if flipped{-1.} else {1.}, 1., 1.))
                 .ease_to(
-                    Transform::from_translation(vec3(rand::thread_rng().gen::<f32>() * 3. - 1.5, 4. + rand::thread_rng().gen::<f32>() * 5., 0.)).with_rotation(Quat::from_rotation_z(rand::thread_rng().gen::<f32>() - 0.5)).with_scale(vec3(if flipped{-1.} else {1.}, 1., 1.)),
+                    Transform::from_translation(vec3(rng.gen::<f32>() * 3. - 1.5, 4. + rng.gen::<f32>() * 5., 0.)).with_rotation(Quat::from_rotation_z(rng.gen::<f32>() - 0.5)).with_scale(vec3(if flipped{-1.} else {1.}, 1., 1.)),
                     EaseFunction::ExponentialOut,
                     EasingType::Once {
                         duration: std::time::Duration::from_secs(1),
@@ -381,13 +395,15 @@ pub fn spawn_warn_particle(
     commands: &mut Commands,
     layout_handles: &mut ResMut<TextureAtlasLayoutHandles>,
     asset_server: &Res<AssetServer>,
+    rng: &mut GameRng,
     pos: Vec3,
 ){
+    let rng = rng.cosmetic();
     let lifetime = 2.;
     let max_offset = 2.;
     let start = pos+vec3(
-        rand::random::<f32>() * max_offset * 2. - max_offset,
-        rand::random::<f32>() * max_offset * 2. - max_offset,
+        rng.gen::<f32>() * max_offset * 2. - max_offset,
+        rng.gen::<f32>() * max_offset * 2. - max_offset,
         0.
     );
     commands.spawn((
@@ -399,10 +415,10 @@ pub fn spawn_warn_particle(
     .with_children(|commands| {
         commands.spawn((
             Name::new("Particle"),
-            emotion_bundle(asset_server, layout_handles, rand::thread_rng().gen_range(0..3) + 3),
-            Transform::from_translation(vec3(0., 0., 0.)).with_rotation(Quat::from_rotation_z(rand::thread_rng().gen::<f32>() - 0.5)).with_scale(Vec3::splat(0.5))
+            emotion_bundle(asset_server, layout_handles, rng.gen_range(0..3) + 3),
+            Transform::from_translation(vec3(0., 0., 0.)).with_rotation(Quat::from_rotation_z(rng.gen::<f32>() - 0.5)).with_scale(Vec3::splat(0.5))
                 .ease_to(
-                    Transform::from_translation(vec3(rand::thread_rng().gen::<f32>() * 3. - 1.5, 4. + rand::thread_rng().gen::<f32>() * 5., 0.)).with_rotation(Quat::from_rotation_z(rand::thread_rng().gen::<f32>() - 0.5))
+                    Transform::from_translation(vec3(rng.gen::<f32>() * 3. - 1.5, 4. + rng.gen::<f32>() * 5., 0.)).with_rotation(Quat::from_rotation_z(rng.gen::<f32>() - 0.5))
                     .with_scale(Vec3::splat(1.5)),
                     EaseFunction::ExponentialOut,
                     EasingType::Once {
@@ -421,6 +437,56 @@ pub fn spawn_warn_particle(
     });
 }
 
+/// A small bat scattering away from `pos`, puffs of them cover the player's transformation.
+pub fn spawn_bat_particle(
+    commands: &mut Commands,
+    layout_handles: &mut ResMut<TextureAtlasLayoutHandles>,
+    asset_server: &Res<AssetServer>,
+    rng: &mut GameRng,
+    pos: Vec3,
+){
+    let rng = rng.cosmetic();
+    let lifetime = 0.6;
+    let angle = rng.gen::<f32>() * PI * 2.;
+    let dist = 10. + rng.gen::<f32>() * 10.;
+    let flipped = angle.cos() < 0.;
+    commands.spawn((
+        TransformBundle::default(),
+        VisibilityBundle::default(),
+        DespawnTimer::seconds(lifetime),
+    ))
+    .insert(Transform::from_translation(vec3(0., 6., 8.) + pos))
+    .with_children(|commands| {
+        commands.spawn((
+            Name::new("Particle"),
+            SpriteBundle{
+                texture: asset_server.load("player/bat.png"),
+                ..default()
+            },
+            TextureAtlas{
+                layout: layout_handles.add_or_load(asset_server, "Bat", TextureAtlasLayout::from_grid(uvec2(16, 16), BAT_FRAMES as u32, 1, None, None)),
+                index: rng.gen_range(0..BAT_FRAMES),
+            },
+            Transform::from_translation(vec3(0., 0., 0.)).with_scale(Vec3::splat(0.5))
+                .ease_to(
+                    Transform::from_translation(vec3(angle.cos(), angle.sin(), 0.) * dist).with_scale(Vec3::splat(0.3)),
+                    EaseFunction::ExponentialOut,
+                    EasingType::Once {
+                        duration: std::time::Duration::from_secs_f32(lifetime),
+                    },
+                )
+        )).insert(
+            Sprite{flip_x: flipped, ..default()}.ease_to(
+                Sprite { color: Color::Srgba(Srgba::new(1., 1., 1., 0.)), flip_x: flipped, ..default() },
+                EaseFunction::ExponentialIn,
+                EasingType::Once {
+                    duration: std::time::Duration::from_secs_f32(lifetime),
+                },
+            )
+        );
+    });
+}
+
 #[derive(Component)]
 pub struct FollowingBloodParticle{
     pub follow: Entity,
@@ -453,9 +519,8 @@ fn spawn_blood_particle(
                 ..default()
             },
             TextureAtlas{
-                layout: layout_handles.add_or_load(&asset_server, "BloodParticle", TextureAtlasLayout::from_grid(uvec2(7, 7), 5, 2, Some(uvec2(1, 1)), None)),
+                layout: layout_handles.add_or_load(asset_server, "BloodParticle", TextureAtlasLayout::from_grid(uvec2(7, 7), 5, 2, Some(uvec2(1, 1)), None)),
                 index,
-                ..default()
             }
         ));
         commands.spawn((
@@ -465,9 +530,8 @@ fn spawn_blood_particle(
                 ..default()
             },
             TextureAtlas{
-                layout: layout_handles.add_or_load(&asset_server, "BloodParticle", TextureAtlasLayout::from_grid(uvec2(7, 7), 5, 2, Some(uvec2(1, 1)), None)),
+                layout: layout_handles.add_or_load(asset_server, "BloodParticle", TextureAtlasLayout::from_grid(uvec2(7, 7), 5, 2, Some(uvec2(1, 1)), None)),
                 index: index + MAX_BLOOD_PARTICLE_SIZE,
-                ..default()
             }
         ));
     }).id()
@@ -477,7 +541,7 @@ const MAX_BLOOD_PARTICLE_SIZE : usize = 5;
 
 
 pub fn spawn_follow_blood_particle(
-    mut commands: &mut Commands,
+    commands: &mut Commands,
     layout_handles: &mut ResMut<TextureAtlasLayoutHandles>,
     asset_server: &Res<AssetServer>,
     follow: Entity,
@@ -1,51 +1,60 @@
-use std::time::Duration;
-
-use bevy::{color::palettes::css::{BLUE, RED}, math::uvec2, prelude::*};
+use bevy::prelude::*;
 use bevy_light_2d::light::AmbientLight2d;
-use pathfinding::num_traits::{Euclid, Signed};
-
-use crate::{characters::animation::AnimationController, core::{camera::plugin::MainCamera, functions::TextureAtlasLayoutHandles, post_processing::PostProcessUniform}, npc::systems::RosesCollected, player::components::{ParentEntity, Player, UpgradeButton}, sounds::components::PlaySoundEvent};
 
-pub const TRANSLATION_DURATION: f32 = 1.0;
-pub const DAY_DURATION: f32 = 15.0;
+use crate::{characters::animation::AnimationController, core::{camera::plugin::MainCamera, post_processing::PostProcessUniform, save::RunSave, tunables::Tunables}, npc::systems::RosesCollected, player::{archetype::{Archetype, Archetypes, SelectedArchetype}, components::{MenuChoice, ParentEntity, Player, PlayerInput}}, sounds::components::PlaySoundEvent};
 
-#[derive(Resource)]
+#[derive(Resource, Clone)]
 pub struct DayCycle {
     pub is_night: bool,
     pub is_translating: bool,
+    /// 1 through the night, 0 through the day, in between at dusk and dawn.
+    pub darkness: f32,
+}
+
+/// Seconds played in the current run, drives the day/night cycle.
+#[derive(Resource, Default, Clone)]
+pub struct RunClock {
+    pub elapsed: f32,
 }
 
 // 0 is morning
-pub fn get_local_time_f(elapsed: f32) -> f32{
-    ((elapsed + TRANSLATION_DURATION * 0.5) % (TRANSLATION_DURATION * 2. + DAY_DURATION * 2.)) / (TRANSLATION_DURATION * 2. + DAY_DURATION * 2.)
+pub fn get_local_time_f(elapsed: f32, tunables: &Tunables) -> f32{
+    ((elapsed + tunables.translation_duration * 0.5) % tunables.cycle_duration()) / tunables.cycle_duration()
 }
 
 
 pub fn update_daycycle(
     mut cycle: ResMut<DayCycle>,
+    mut clock: ResMut<RunClock>,
     mut post_process: Query<&mut PostProcessUniform>,
     mut cam: Query<&mut AmbientLight2d, With<MainCamera>>,
     time: Res<Time<Virtual>>,
+    tunables: Res<Tunables>,
     mut pc_q: Query<&mut AnimationController, With<Player>>
 ) {
-    let cycle_time = (time.elapsed_seconds() + TRANSLATION_DURATION * 2. + DAY_DURATION * 2.) % (TRANSLATION_DURATION * 2. + DAY_DURATION * 2.);
-    let is_night_raw = cycle_time < (TRANSLATION_DURATION + DAY_DURATION);
-    let local_time = cycle_time % (TRANSLATION_DURATION + DAY_DURATION);
+    clock.elapsed += time.delta_seconds();
+    let day_duration = tunables.day_duration;
+    let translation_duration = tunables.translation_duration;
+    let cycle_time = (clock.elapsed + tunables.cycle_duration()) % tunables.cycle_duration();
+    let is_night_raw = cycle_time < (translation_duration + day_duration);
+    let local_time = cycle_time % (translation_duration + day_duration);
     cycle.is_night = is_night_raw;
-    let mut light = cam.single_mut();
     cycle.is_translating = false;
-    if local_time > DAY_DURATION {
-        let translation = (local_time - DAY_DURATION) / TRANSLATION_DURATION;
-        let v = if is_night_raw {1.-translation} else {translation};
-        post_process.single_mut().daytime = v;
-        light.brightness = (1. - v) * 0.8 + 0.2;
+    let daytime = if local_time > day_duration {
+        let translation = (local_time - day_duration) / translation_duration;
         cycle.is_translating = true;
         if translation > 0.5 {
             cycle.is_night = !cycle.is_night;
         }
-    } else {
-        post_process.single_mut().daytime = if cycle.is_night {1.} else {0.};
-        light.brightness = if cycle.is_night {0.2} else {1.};
+        if is_night_raw {1.-translation} else {translation}
+    } else if cycle.is_night {1.} else {0.};
+    cycle.darkness = daytime;
+    // Headless runs have neither the camera nor the post process
+    if let Ok(mut post_process) = post_process.get_single_mut() {
+        post_process.daytime = daytime;
+    }
+    if let Ok(mut light) = cam.get_single_mut() {
+        light.brightness = (1. - daytime) * 0.8 + 0.2;
     }
     for mut pc in pc_q.iter_mut(){
         if cycle.is_night {
@@ -56,34 +65,61 @@ pub fn update_daycycle(
     }
 }
 
-#[derive(Event, Debug)]
-pub struct PauseEvent;
-
-#[derive(States, Default, Debug, Clone, PartialEq, Eq, Hash)]
+#[derive(States, Default, Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum GameState {
     #[default]
-    InGame,
-    Pause,
+    MainMenu,
+    Playing,
+    Paused,
+    LevelUp,
+    GameOver,
+    Victory,
+    Settings,
+    /// Stats of the run that just ended, after the death or victory countdown.
+    Summary,
+}
+
+impl GameState {
+    pub const ALL: [GameState; 8] = [
+        GameState::MainMenu,
+        GameState::Playing,
+        GameState::Paused,
+        GameState::LevelUp,
+        GameState::GameOver,
+        GameState::Victory,
+        GameState::Settings,
+        GameState::Summary,
+    ];
+
+    /// Whether the world clock runs in this state.
+    pub fn is_running(&self) -> bool {
+        matches!(self, GameState::Playing)
+    }
 }
 
-pub fn pause_game(
+/// Runs on entering every state, so `Time<Virtual>` always matches the current one.
+pub fn sync_virtual_time(
     state: Res<State<GameState>>,
-    mut next_state: ResMut<NextState<GameState>>,
     mut time: ResMut<Time<Virtual>>,
-    mut pause_event: EventReader<PauseEvent>,
 ) {
-    let t = pause_event.read();
-    for _pause in t {
-        match state.get() {
-            GameState::InGame => {
-                next_state.set(GameState::Pause);
-                time.pause();
-            },
-            GameState::Pause => {
-                next_state.set(GameState::InGame);
-                time.unpause();
-            },
-        }
+    if state.get().is_running() {
+        time.unpause();
+    } else {
+        time.pause();
+    }
+}
+
+pub fn toggle_pause(
+    input: Res<PlayerInput>,
+    state: Res<State<GameState>>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    if !input.pause {return}
+    match state.get() {
+        GameState::Playing => next_state.set(GameState::Paused),
+        GameState::Paused => next_state.set(GameState::Playing),
+        GameState::Settings => next_state.set(GameState::Paused),
+        _ => {}
     }
 }
 
@@ -151,12 +187,28 @@ pub fn update_score(
 #[derive(Component)]
 pub struct StartButton;
 
+#[derive(Component)]
+pub struct ContinueButton;
+
+#[derive(Component)]
+pub struct StarterScreen;
+
+#[derive(Component)]
+pub struct ArchetypeButton;
+
+#[derive(Component)]
+pub struct ArchetypeLabel;
+
+#[derive(Component)]
+pub struct ArchetypeDescription;
+
 pub fn spawn_starter_screen(
     mut commands: Commands,
     asset_server: Res<AssetServer>,
-    mut pause_event: EventWriter<PauseEvent>,
+    archetypes: Res<Archetypes>,
+    selected: Res<SelectedArchetype>,
 ) {
-    pause_event.send(PauseEvent);
+    let archetype = archetypes.get(selected.index);
     let font = asset_server.load("fonts/Monocraft.ttf");
     let parent = commands.spawn((
         ImageBundle {
@@ -174,6 +226,7 @@ pub fn spawn_starter_screen(
             ..default()
         },
         Name::new("StarterScreen"),
+        StarterScreen,
     )).with_children(|parent| {
         parent.spawn(TextBundle {
             style: Style {
@@ -190,6 +243,7 @@ pub fn spawn_starter_screen(
 
                     Controls:
                     WASD - movement, LShift - Dash.
+                    A/D - choose your vampire.
                     If you are experiencing  lags, press F4
                     ",
                     TextStyle { font: font.clone_weak(), font_size: 16., color: Color::srgb_u8(169, 96, 45) })],
@@ -198,6 +252,48 @@ pub fn spawn_starter_screen(
             ..default()
         });
     }).id();
+    let archetype_button = commands.spawn((ButtonBundle {
+        style: Style {
+            width: Val::Px(150.),
+            height: Val::Px(30.),
+            justify_items: JustifyItems::Center,
+            justify_content: JustifyContent::Center,
+            align_self: AlignSelf::Center,
+            justify_self: JustifySelf::Center,
+            ..default()
+        },
+        image: UiImage::from(asset_server.load("button.png")),
+        ..default()
+    },
+    ArchetypeButton,
+    )).with_children(|parent| {
+        parent.spawn((TextBundle {
+            style: Style {
+                align_self: AlignSelf::Center,
+                justify_self: JustifySelf::Center,
+                ..default()
+            },
+            text: Text {
+                sections: vec![TextSection::new(archetype_label(&archetype), TextStyle { font: font.clone_weak(), font_size: 16., color: Color::srgb_u8(169, 96, 45) })],
+                ..default()
+            },
+            ..default()
+        }, ArchetypeLabel));
+    }).id();
+    let description = commands.spawn((TextBundle {
+        style: Style {
+            align_self: AlignSelf::Center,
+            justify_self: JustifySelf::Center,
+            margin: UiRect::top(Val::Px(10.)),
+            ..default()
+        },
+        text: Text {
+            sections: vec![TextSection::new(archetype.description.clone(), TextStyle { font: font.clone_weak(), font_size: 12., color: Color::srgb_u8(169, 96, 45) })],
+            ..default()
+        },
+        ..default()
+    }, ArchetypeDescription)).id();
+    commands.entity(parent).push_children(&[archetype_button, description]);
     let child = commands.spawn((ButtonBundle {
         style: Style {
             width: Val::Px(150.),
@@ -222,28 +318,79 @@ pub fn spawn_starter_screen(
                 ..default()
             },
             text: Text {
-                sections: vec![TextSection::new("Start", TextStyle { font, font_size: 16., color: Color::srgb_u8(169, 96, 45) })],
+                sections: vec![TextSection::new("Start", TextStyle { font: font.clone_weak(), font_size: 16., color: Color::srgb_u8(169, 96, 45) })],
                 ..default()
             },
             ..default()
         });
     }).id();
     commands.entity(parent).add_child(child);
+    if RunSave::exists() {
+        let child = commands.spawn((ButtonBundle {
+            style: Style {
+                width: Val::Px(150.),
+                height: Val::Px(30.),
+                justify_items: JustifyItems::Center,
+                justify_content: JustifyContent::Center,
+                align_self: AlignSelf::Center,
+                justify_self: JustifySelf::Center,
+                margin: UiRect::top(Val::Px(10.)),
+                ..default()
+            },
+            image: UiImage::from(asset_server.load("button.png")),
+            ..default()
+        },
+        ContinueButton,
+        )).with_children(|parent| {
+            parent.spawn(TextBundle {
+                style: Style {
+                    align_self: AlignSelf::Center,
+                    justify_self: JustifySelf::Center,
+                    ..default()
+                },
+                text: Text {
+                    sections: vec![TextSection::new("Continue", TextStyle { font, font_size: 16., color: Color::srgb_u8(169, 96, 45) })],
+                    ..default()
+                },
+                ..default()
+            });
+        }).id();
+        commands.entity(parent).add_child(child);
+    }
 }
 
 pub fn interact_start_button(
-    mut commands: Commands,
-    mut button_q: Query<(&Interaction, &mut UiImage, &ParentEntity), (With<StartButton>, Changed<Interaction>)>,
-    mut pause_event: EventWriter<PauseEvent>,
+    mut button_q: Query<(&Interaction, &mut UiImage), (With<StartButton>, Changed<Interaction>)>,
+    mut input: ResMut<PlayerInput>,
+    mut play_sound: EventWriter<PlaySoundEvent>,
+    asset_server: Res<AssetServer>,
+) {
+    if let Ok((interaction, mut image)) = button_q.get_single_mut() {
+        match *interaction {
+            Interaction::Pressed => {
+                input.menu = Some(MenuChoice::Start);
+            }
+            Interaction::Hovered => {
+                play_sound.send(PlaySoundEvent::Select);
+                *image = UiImage::from(asset_server.load("select_button.png"));
+            }
+            Interaction::None => {
+                *image = UiImage::from(asset_server.load("button.png"));
+            }
+        }
+    }
+}
+
+pub fn interact_continue_button(
+    mut button_q: Query<(&Interaction, &mut UiImage), (With<ContinueButton>, Changed<Interaction>)>,
+    mut input: ResMut<PlayerInput>,
     mut play_sound: EventWriter<PlaySoundEvent>,
     asset_server: Res<AssetServer>,
 ) {
-    if let Ok((interaction, mut image, parent_entity)) = button_q.get_single_mut() {
+    if let Ok((interaction, mut image)) = button_q.get_single_mut() {
         match *interaction {
             Interaction::Pressed => {
-                commands.entity(parent_entity.entity).despawn_recursive();
-                play_sound.send(PlaySoundEvent::Selected);
-                pause_event.send(PauseEvent);
+                input.menu = Some(MenuChoice::Continue);
             }
             Interaction::Hovered => {
                 play_sound.send(PlaySoundEvent::Select);
@@ -254,4 +401,72 @@ pub fn interact_start_button(
             }
         }
     }
-}
\ No newline at end of file
+}
+
+fn archetype_label(archetype: &Archetype) -> String {
+    format!("< {} >", archetype.name)
+}
+
+pub fn interact_archetype_button(
+    mut button_q: Query<(&Interaction, &mut UiImage), (With<ArchetypeButton>, Changed<Interaction>)>,
+    mut input: ResMut<PlayerInput>,
+    mut play_sound: EventWriter<PlaySoundEvent>,
+    asset_server: Res<AssetServer>,
+) {
+    if let Ok((interaction, mut image)) = button_q.get_single_mut() {
+        match *interaction {
+            Interaction::Pressed => {
+                input.menu = Some(MenuChoice::Archetype);
+            }
+            Interaction::Hovered => {
+                play_sound.send(PlaySoundEvent::Select);
+                *image = UiImage::from(asset_server.load("select_button.png"));
+            }
+            Interaction::None => {
+                *image = UiImage::from(asset_server.load("button.png"));
+            }
+        }
+    }
+}
+
+/// Clicking the archetype button steps forward, left/right step either way.
+pub fn select_archetype(
+    input: Res<PlayerInput>,
+    archetypes: Res<Archetypes>,
+    mut selected: ResMut<SelectedArchetype>,
+    mut play_sound: EventWriter<PlaySoundEvent>,
+) {
+    let count = archetypes.count();
+    // the file may have lost some since the last pick
+    if selected.index >= count {selected.index = 0}
+    let dir = if input.menu == Some(MenuChoice::Archetype) {1} else {input.menu_adjust};
+    if dir == 0 {return}
+    selected.index = (selected.index as i32 + dir).rem_euclid(count as i32) as usize;
+    play_sound.send(PlaySoundEvent::Select);
+}
+
+pub fn update_archetype_text(
+    archetypes: Res<Archetypes>,
+    selected: Res<SelectedArchetype>,
+    mut label: Query<&mut Text, (With<ArchetypeLabel>, Without<ArchetypeDescription>)>,
+    mut description: Query<&mut Text, With<ArchetypeDescription>>,
+) {
+    if !archetypes.is_changed() && !selected.is_changed() {return}
+    let archetype = archetypes.get(selected.index);
+    if let Ok(mut label) = label.get_single_mut() {
+        label.sections[0].value = archetype_label(&archetype);
+    }
+    if let Ok(mut description) = description.get_single_mut() {
+        description.sections[0].value = archetype.description;
+    }
+}
+
+pub fn start_game(
+    input: Res<PlayerInput>,
+    mut next_state: ResMut<NextState<GameState>>,
+    mut play_sound: EventWriter<PlaySoundEvent>,
+) {
+    if input.menu != Some(MenuChoice::Start) {return}
+    play_sound.send(PlaySoundEvent::Selected);
+    next_state.set(GameState::Playing);
+}
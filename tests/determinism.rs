@@ -0,0 +1,61 @@
+use bevy::prelude::*;
+use bevy_jam::{
+    core::{damage::Health, headless::Simulation, input::Action},
+    npc::components::NpcState,
+    player::components::Player,
+};
+
+const SEED: u64 = 1234;
+const FRAMES: usize = 600;
+
+/// Everything a diverging run would show up in.
+#[derive(Debug, PartialEq)]
+struct Snapshot {
+    player: Vec3,
+    health: f32,
+    xp: f32,
+    score: f32,
+    npcs: Vec<(Vec3, NpcState)>,
+}
+
+fn snapshot(sim: &mut Simulation) -> Snapshot {
+    let world = sim.app.world_mut();
+    let (transform, health, player) = world
+        .query::<(&Transform, &Health, &Player)>()
+        .single(world);
+    let (player, health, xp, score) = (transform.translation, health.current, player.xp, player.score);
+    let npcs = world.query::<(&Transform, &NpcState)>()
+        .iter(world)
+        .map(|(t, state)| (t.translation, *state))
+        .collect();
+    Snapshot {player, health, xp, score, npcs}
+}
+
+/// Walks around a bit so the player, the npcs and the physics all have something to do.
+fn play(seed: u64) -> Snapshot {
+    let mut sim = Simulation::new(seed);
+    assert!(sim.wait_for_map(), "map did not load");
+    assert!(sim.trespassable().cells.iter().flatten().any(|&open| open), "pathfinding grid has no walkable cell");
+    assert_eq!(sim.roses().collected, 0);
+    assert!(sim.player().is_some_and(|player| !player.is_dead), "no living player at the start");
+    for (i, action) in [Action::MoveRight, Action::MoveUp, Action::MoveLeft, Action::MoveDown].into_iter().cycle().take(8).enumerate() {
+        sim.press(action);
+        if i % 3 == 0 {
+            sim.press(Action::Dash);
+        }
+        sim.step(FRAMES / 8);
+        sim.release(action);
+        sim.release(Action::Dash);
+    }
+    assert!(sim.npc_states().iter().any(|state| *state != NpcState::Dead), "every npc is dead after a short walk");
+    assert!(sim.roses().collected <= sim.roses().max);
+    snapshot(&mut sim)
+}
+
+#[test]
+fn same_seed_same_run() {
+    let first = play(SEED);
+    let second = play(SEED);
+    assert!(!first.npcs.is_empty(), "no npcs spawned, nothing was compared");
+    assert_eq!(first, second);
+}